@@ -0,0 +1,43 @@
+// Stamp the build with a hash of its sources, so the `tlp` daemon and
+// `tlp-guard` only talk to each other when they were built from the same code.
+
+use sha2::{Digest, Sha256};
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+
+const INPUTS: [&str; 4] = ["src", "Cargo.toml", "Cargo.lock", "build.rs"];
+
+fn hash_path(hasher: &mut Sha256, path: &Path) {
+    if path.is_dir() {
+        let mut entries: Vec<_> = fs::read_dir(path)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .map(|e| e.path())
+            .collect();
+        entries.sort();
+        for entry in entries {
+            hash_path(hasher, &entry);
+        }
+    } else if let Ok(bytes) = fs::read(path) {
+        hasher.update(path.to_string_lossy().as_bytes());
+        hasher.update((bytes.len() as u64).to_le_bytes());
+        hasher.update(&bytes);
+    }
+}
+
+fn main() {
+    let mut hasher = Sha256::new();
+    hasher.update(env!("CARGO_PKG_VERSION"));
+    for input in INPUTS {
+        println!("cargo:rerun-if-changed={input}");
+        hash_path(&mut hasher, Path::new(input));
+    }
+    let digest = hasher.finalize();
+    let hex = digest[..8].iter().fold(String::new(), |mut out, b| {
+        let _ = write!(out, "{b:02x}");
+        out
+    });
+    println!("cargo:rustc-env=TLP_BUILD_ID={hex}");
+}
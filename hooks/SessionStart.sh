@@ -1,10 +1,15 @@
 #!/usr/bin/env bash
-# SessionStart: emit TLP conventions.
+# SessionStart: emit TLP conventions and export the session id to CLI tools.
 set -euo pipefail
 
 MODULE_ROOT="$(command cd "$(dirname "$0")/.." && pwd)"
 PROJECT_ROOT="${FORGE_ROOT:-$(command cd "$MODULE_ROOT/../.." && pwd)}"
 
+source "$MODULE_ROOT/bin/_build.sh"
+if ensure_built tlp; then
+    "$BIN_DIR/tlp" session-start || true  # Audit tagging only: never fail the session
+fi
+
 FORGE_LOAD="$PROJECT_ROOT/Modules/forge-load/src"
 if [ -f "$FORGE_LOAD/load.sh" ]; then
     source "$FORGE_LOAD/load.sh"
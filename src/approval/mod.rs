@@ -0,0 +1,293 @@
+use crate::state;
+use crate::tlp;
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::fmt::Write as _;
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Environment variable carrying a `tlp approve` token into safe-read/safe-write.
+pub const TOKEN_ENV: &str = "TLP_APPROVAL_TOKEN";
+
+/// Version tag leading every token and every signed message.
+const TOKEN_VERSION: &str = "tlp1";
+
+/// A user's approval to read AMBER files matching `pattern`.
+///
+/// A grant is scoped to a session, a deadline, or both, and signed with the
+/// approval key. Unsigned or tampered grants are ignored. The signature proves
+/// integrity only: whoever can read the key, the agent's Bash included, can
+/// mint one.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Grant {
+    /// Absolute file path, `dir/**` prefix, or `*.ext` pattern.
+    pub pattern: String,
+    pub session_id: Option<String>,
+    /// Unix seconds after which the grant no longer applies.
+    pub expires: Option<u64>,
+    pub granted: u64,
+    /// `#tlp/red/<compartment>` sections left visible in covered files.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub reveal: Vec<String>,
+    /// Hex HMAC-SHA256 over pattern, session, expiry and reveals.
+    #[serde(default)]
+    pub mac: String,
+}
+
+impl Grant {
+    /// True if the grant applies to this session at this time.
+    pub fn is_active(&self, session_id: Option<&str>, now: u64) -> bool {
+        let session_ok = self
+            .session_id
+            .as_deref()
+            .is_none_or(|s| session_id == Some(s));
+        let time_ok = self.expires.is_none_or(|t| now < t);
+        session_ok && time_ok
+    }
+
+    /// True if the grant's pattern matches an absolute file path.
+    pub fn covers(&self, abs_path: &str) -> bool {
+        tlp::matches_pattern(abs_path, &self.pattern)
+    }
+
+    fn message(&self) -> String {
+        let mut message = format!(
+            "{TOKEN_VERSION}\n{}\n{}\n{}",
+            self.pattern,
+            self.session_id.as_deref().unwrap_or_default(),
+            self.expires.map(|t| t.to_string()).unwrap_or_default()
+        );
+        // Grants without reveals sign the same message as before reveals existed
+        if !self.reveal.is_empty() {
+            message.push('\n');
+            message.push_str(&self.reveal.join(","));
+        }
+        message
+    }
+
+    fn hmac(&self, key: &[u8]) -> HmacSha256 {
+        let mut mac = HmacSha256::new_from_slice(key).expect("HMAC accepts any key length");
+        mac.update(self.message().as_bytes());
+        mac
+    }
+
+    /// Sign the grant with the approval key.
+    pub fn sign(&mut self, key: &[u8]) {
+        self.mac = to_hex(&self.hmac(key).finalize().into_bytes());
+    }
+
+    /// True if the grant carries a valid signature for this key.
+    pub fn verify(&self, key: &[u8]) -> bool {
+        from_hex(&self.mac).is_some_and(|mac| self.hmac(key).verify_slice(&mac).is_ok())
+    }
+
+    /// Encode as a token the user can hand to `safe-read --token`:
+    /// `tlp1.<expires>.<session>.<hex pattern>.<hex mac>`, followed by
+    /// `.<hex reveals>` when the grant reveals compartments.
+    pub fn token(&self) -> String {
+        let mut token = format!(
+            "{TOKEN_VERSION}.{}.{}.{}.{}",
+            self.expires.map(|t| t.to_string()).unwrap_or_default(),
+            self.session_id.as_deref().unwrap_or_default(),
+            to_hex(self.pattern.as_bytes()),
+            self.mac
+        );
+        if !self.reveal.is_empty() {
+            token.push('.');
+            token.push_str(&to_hex(self.reveal.join(",").as_bytes()));
+        }
+        token
+    }
+
+    /// Decode a token produced by `token`. The signature is not checked here.
+    pub fn from_token(token: &str) -> Option<Grant> {
+        let parts: Vec<&str> = token.trim().split('.').collect();
+        // Reveals ride along as an optional sixth field
+        let (fields, reveal) = match parts.len() {
+            5 => (&parts[..], ""),
+            6 => (&parts[..5], parts[5]),
+            _ => return None,
+        };
+        let [version, expires, session, pattern, mac] = fields[..] else {
+            return None;
+        };
+        let reveal = String::from_utf8(from_hex(reveal)?).ok()?;
+        if version != TOKEN_VERSION {
+            return None;
+        }
+        let expires = if expires.is_empty() {
+            None
+        } else {
+            Some(expires.parse().ok()?)
+        };
+        Some(Grant {
+            pattern: String::from_utf8(from_hex(pattern)?).ok()?,
+            session_id: (!session.is_empty()).then(|| session.to_string()),
+            expires,
+            granted: 0,
+            reveal: reveal
+                .split(',')
+                .filter(|r| !r.is_empty())
+                .map(String::from)
+                .collect(),
+            mac: mac.to_string(),
+        })
+    }
+}
+
+/// Location of the approvals file in the state directory.
+pub fn grants_path() -> Option<PathBuf> {
+    Some(state::state_dir()?.join("approvals.json"))
+}
+
+/// Location of the approval signing key in the state directory.
+pub fn key_path() -> Option<PathBuf> {
+    Some(state::state_dir()?.join("approval.key"))
+}
+
+/// Location of the file recording the most recent session id.
+pub fn session_path() -> Option<PathBuf> {
+    Some(state::state_dir()?.join("session"))
+}
+
+/// Load the signing key. None if it has not been created yet.
+pub fn load_key(path: &Path) -> Option<Vec<u8>> {
+    from_hex(fs::read_to_string(path).ok()?.trim()).filter(|k| !k.is_empty())
+}
+
+/// Load the signing key, creating a random one (mode 0600) on first use.
+pub fn load_or_create_key(path: &Path) -> io::Result<Vec<u8>> {
+    if let Some(key) = load_key(path) {
+        return Ok(key);
+    }
+
+    let mut key = vec![0u8; 32];
+    fs::File::open("/dev/urandom")?.read_exact(&mut key)?;
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    io::Write::write_all(&mut options.open(path)?, to_hex(&key).as_bytes())?;
+    Ok(key)
+}
+
+/// Load all grants. A missing or unreadable file means no grants.
+pub fn load(path: &Path) -> Vec<Grant> {
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+/// Replace the stored grants, dropping any that have expired.
+pub fn save(path: &Path, grants: &[Grant], now: u64) -> io::Result<()> {
+    let live: Vec<&Grant> = grants
+        .iter()
+        .filter(|g| g.expires.is_none_or(|t| now < t))
+        .collect();
+    let json = serde_json::to_string_pretty(&live).map_err(io::Error::other)?;
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    // Write-then-rename so a concurrent reader never sees a half-written file
+    let tmp = path.with_extension("json.tmp");
+    fs::write(&tmp, json)?;
+    fs::rename(&tmp, path)
+}
+
+/// True if any validly signed, active grant covers the file.
+pub fn is_approved(
+    grants: &[Grant],
+    key: &[u8],
+    abs_path: &str,
+    session_id: Option<&str>,
+    now: u64,
+) -> bool {
+    grants
+        .iter()
+        .any(|g| g.verify(key) && g.is_active(session_id, now) && g.covers(abs_path))
+}
+
+/// Validly signed, active grants covering the file: a presented token and
+/// the recorded grants. Without a signing key there are none (fail closed).
+fn covering(file_path: &str, session_id: Option<&str>, token: Option<&str>) -> Vec<Grant> {
+    let Some(key) = key_path().and_then(|p| load_key(&p)) else {
+        return Vec::new();
+    };
+    let abs = std::path::absolute(file_path).map_or_else(
+        |_| file_path.to_string(),
+        |p| p.to_string_lossy().to_string(),
+    );
+    let now = state::now();
+
+    let mut grants: Vec<Grant> = token.and_then(Grant::from_token).into_iter().collect();
+    if let Some(path) = grants_path() {
+        grants.extend(load(&path));
+    }
+    grants.retain(|g| is_approved(std::slice::from_ref(g), &key, &abs, session_id, now));
+    grants
+}
+
+/// True if the file may be disclosed: a presented token or a recorded grant
+/// covers it. Without a signing key nothing is approved (fail closed).
+pub fn is_authorized(file_path: &str, session_id: Option<&str>, token: Option<&str>) -> bool {
+    !covering(file_path, session_id, token).is_empty()
+}
+
+/// Compartments the user revealed for the file, through `tlp approve` or
+/// `tlp allow --reveal`. Only grants that cover it count.
+pub fn revealed(file_path: &str, session_id: Option<&str>, token: Option<&str>) -> Vec<String> {
+    covering(file_path, session_id, token)
+        .into_iter()
+        .flat_map(|g| g.reveal)
+        .collect()
+}
+
+/// Turn a user-supplied path into a grant pattern: globs pass through
+/// (made absolute unless they are `*.ext`), directories become `dir/**`,
+/// files become their absolute path.
+pub fn pattern_for(input: &str) -> io::Result<String> {
+    if input.starts_with('*') && !input.contains('/') {
+        return Ok(input.to_string());
+    }
+
+    let abs = std::path::absolute(input)?;
+    let abs_str = abs.to_string_lossy().trim_end_matches('/').to_string();
+
+    if input.contains('*') {
+        Ok(abs_str)
+    } else if abs.is_dir() {
+        Ok(format!("{abs_str}/**"))
+    } else {
+        Ok(abs_str)
+    }
+}
+
+pub(crate) fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().fold(String::new(), |mut out, b| {
+        let _ = write!(out, "{b:02x}");
+        out
+    })
+}
+
+pub(crate) fn from_hex(s: &str) -> Option<Vec<u8>> {
+    if !s.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(s.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,199 @@
+use super::*;
+use tempfile::tempdir;
+
+fn grant(pattern: &str, session_id: Option<&str>, expires: Option<u64>) -> Grant {
+    Grant {
+        pattern: pattern.to_string(),
+        session_id: session_id.map(String::from),
+        expires,
+        granted: 0,
+        reveal: Vec::new(),
+        mac: String::new(),
+    }
+}
+
+const KEY: &[u8] = b"test-key";
+
+fn signed(pattern: &str, session_id: Option<&str>, expires: Option<u64>) -> Grant {
+    let mut g = grant(pattern, session_id, expires);
+    g.sign(KEY);
+    g
+}
+
+#[test]
+fn test_session_grant_only_applies_to_its_session() {
+    let g = grant("/vault/a.md", Some("s1"), None);
+    assert!(g.is_active(Some("s1"), 100));
+    assert!(!g.is_active(Some("s2"), 100));
+    assert!(!g.is_active(None, 100));
+}
+
+#[test]
+fn test_timed_grant_expires() {
+    let g = grant("/vault/a.md", None, Some(200));
+    assert!(g.is_active(None, 100));
+    assert!(g.is_active(Some("any"), 199));
+    assert!(!g.is_active(None, 200));
+}
+
+#[test]
+fn test_session_and_time_both_required() {
+    let g = grant("/vault/a.md", Some("s1"), Some(200));
+    assert!(g.is_active(Some("s1"), 100));
+    assert!(!g.is_active(Some("s1"), 300));
+    assert!(!g.is_active(Some("s2"), 100));
+}
+
+#[test]
+fn test_covers_file_dir_and_extension() {
+    assert!(grant("/vault/a.md", None, None).covers("/vault/a.md"));
+    assert!(!grant("/vault/a.md", None, None).covers("/vault/a.md.bak"));
+    assert!(grant("/vault/Journals/**", None, None).covers("/vault/Journals/2026/today.md"));
+    assert!(!grant("/vault/Journals/**", None, None).covers("/vault/JournalsOld/x.md"));
+    assert!(grant("*.csv", None, None).covers("/vault/data/export.csv"));
+}
+
+#[test]
+fn test_is_approved() {
+    let grants = vec![
+        signed("/vault/Journals/**", Some("s1"), None),
+        signed("/vault/Identity.md", None, Some(50)),
+    ];
+    assert!(is_approved(
+        &grants,
+        KEY,
+        "/vault/Journals/a.md",
+        Some("s1"),
+        100
+    ));
+    assert!(!is_approved(
+        &grants,
+        KEY,
+        "/vault/Journals/a.md",
+        Some("s2"),
+        100
+    ));
+    assert!(!is_approved(
+        &grants,
+        KEY,
+        "/vault/Identity.md",
+        Some("s1"),
+        100
+    ));
+    assert!(is_approved(&grants, KEY, "/vault/Identity.md", None, 10));
+}
+
+#[test]
+fn test_unsigned_or_tampered_grants_are_ignored() {
+    let unsigned = grant("/vault/a.md", None, None);
+    assert!(!is_approved(&[unsigned], KEY, "/vault/a.md", None, 0));
+
+    let mut widened = signed("/vault/a.md", None, Some(50));
+    widened.pattern = "/vault/**".to_string();
+    assert!(!widened.verify(KEY));
+
+    let mut extended = signed("/vault/a.md", None, Some(50));
+    extended.expires = None;
+    assert!(!extended.verify(KEY));
+
+    assert!(!signed("/vault/a.md", None, None).verify(b"other-key"));
+}
+
+#[test]
+fn test_token_roundtrip() {
+    let g = signed("/vault/Journals/**", Some("s1"), Some(500));
+    let parsed = Grant::from_token(&g.token()).unwrap();
+    assert_eq!(parsed.pattern, g.pattern);
+    assert_eq!(parsed.session_id, g.session_id);
+    assert_eq!(parsed.expires, g.expires);
+    assert!(parsed.verify(KEY));
+
+    let open = signed("*.md", None, None);
+    assert!(Grant::from_token(&open.token()).unwrap().verify(KEY));
+}
+
+#[test]
+fn test_reveals_are_signed_and_carried_in_tokens() {
+    let mut g = grant("/vault/a.md", None, Some(500));
+    g.reveal = vec!["health".into(), "family".into()];
+    g.sign(KEY);
+    let parsed = Grant::from_token(&g.token()).unwrap();
+    assert_eq!(parsed.reveal, g.reveal);
+    assert!(parsed.verify(KEY));
+
+    // Widening the reveals breaks the signature
+    let mut widened = g.clone();
+    widened.reveal.push("finance".into());
+    assert!(!widened.verify(KEY));
+    let mut dropped = signed("/vault/a.md", None, Some(500));
+    dropped.reveal = vec!["health".into()];
+    assert!(!dropped.verify(KEY));
+}
+
+#[test]
+fn test_malformed_tokens_rejected() {
+    assert!(Grant::from_token("").is_none());
+    assert!(Grant::from_token("tlp2.1..2f.00").is_none());
+    assert!(Grant::from_token("tlp1.x..2f.00").is_none());
+    assert!(Grant::from_token("tlp1.1..zz.00").is_none());
+    assert!(Grant::from_token("tlp1.1.2f.00").is_none());
+}
+
+#[test]
+fn test_key_created_once() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("state/approval.key");
+    assert!(load_key(&path).is_none());
+
+    let key = load_or_create_key(&path).unwrap();
+    assert_eq!(key.len(), 32);
+    assert_eq!(load_or_create_key(&path).unwrap(), key);
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+}
+
+#[test]
+fn test_save_and_load_roundtrip_drops_expired() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("state/approvals.json");
+    let grants = vec![
+        grant("/vault/a.md", Some("s1"), None),
+        grant("/vault/b.md", None, Some(50)),
+    ];
+
+    save(&path, &grants, 100).unwrap();
+    assert_eq!(load(&path), vec![grants[0].clone()]);
+}
+
+#[test]
+fn test_load_missing_file_is_empty() {
+    let dir = tempdir().unwrap();
+    assert!(load(&dir.path().join("approvals.json")).is_empty());
+}
+
+#[test]
+fn test_pattern_for_directory_and_file() {
+    let dir = tempdir().unwrap();
+    fs::create_dir_all(dir.path().join("Journals")).unwrap();
+    fs::write(dir.path().join("note.md"), "x").unwrap();
+    let base = dir.path().to_string_lossy();
+
+    assert_eq!(
+        pattern_for(&format!("{base}/Journals")).unwrap(),
+        format!("{base}/Journals/**")
+    );
+    assert_eq!(
+        pattern_for(&format!("{base}/note.md")).unwrap(),
+        format!("{base}/note.md")
+    );
+    assert_eq!(
+        pattern_for(&format!("{base}/Journals/**")).unwrap(),
+        format!("{base}/Journals/**")
+    );
+    assert_eq!(pattern_for("*.md").unwrap(), "*.md");
+}
@@ -0,0 +1,222 @@
+use crate::config;
+use crate::state;
+use crate::tlp::Classification;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// What a guard did with a tool call or CLI request.
+#[derive(Debug, Default, Serialize, Deserialize, PartialEq, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum Decision {
+    #[default]
+    Allow,
+    Deny,
+    /// Content was disclosed only through redaction.
+    Redact,
+    /// The user was asked to decide.
+    Ask,
+}
+
+impl Decision {
+    /// Parse a decision name as written in the log.
+    pub fn parse(s: &str) -> Option<Decision> {
+        match s {
+            "allow" => Some(Decision::Allow),
+            "deny" => Some(Decision::Deny),
+            "redact" => Some(Decision::Redact),
+            "ask" => Some(Decision::Ask),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Decision::Allow => "allow",
+            Decision::Deny => "deny",
+            Decision::Redact => "redact",
+            Decision::Ask => "ask",
+        }
+    }
+}
+
+/// One line of the audit log.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Record {
+    pub timestamp: String,
+    pub session_id: Option<String>,
+    pub tool: String,
+    pub path: String,
+    pub rel_path: String,
+    pub level: String,
+    pub rule: String,
+    pub decision: Decision,
+    pub secrets: usize,
+    pub redactions: usize,
+    /// Monitor mode: the decision was recorded but not enforced.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub monitor: bool,
+    /// Reason the user gave for a break-glass override that let the call through.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub break_glass: Option<String>,
+}
+
+impl Record {
+    /// Start a record for a classified file, timestamped now.
+    pub fn new(tool: &str, path: &str, c: &Classification, decision: Decision) -> Self {
+        let abs = std::path::absolute(path)
+            .map_or_else(|_| path.to_string(), |p| p.to_string_lossy().to_string());
+        Record {
+            timestamp: rfc3339(state::now()),
+            tool: tool.to_string(),
+            path: abs,
+            rel_path: c.rel_path.clone(),
+            level: c.level.as_str().to_string(),
+            rule: c.rule.clone(),
+            decision,
+            ..Record::default()
+        }
+    }
+}
+
+/// Criteria for `tlp audit`. Unset fields match everything.
+#[derive(Debug, Default)]
+pub struct Filter {
+    pub session: Option<String>,
+    /// Substring of the absolute or vault-relative path.
+    pub path: Option<String>,
+    pub decision: Option<Decision>,
+}
+
+impl Filter {
+    pub fn matches(&self, record: &Record) -> bool {
+        self.session
+            .as_ref()
+            .is_none_or(|s| record.session_id.as_ref() == Some(s))
+            && self.path.as_ref().is_none_or(|p| {
+                record.path.contains(p.as_str()) || record.rel_path.contains(p.as_str())
+            })
+            && self.decision.is_none_or(|d| record.decision == d)
+    }
+}
+
+/// Resolve the log file: the configured `path` (`~/` expanded), or
+/// `audit.jsonl` in the state directory.
+pub fn log_path(settings: &config::Audit) -> Option<PathBuf> {
+    match &settings.path {
+        Some(p) => match p.strip_prefix("~") {
+            Ok(rest) => Some(PathBuf::from(std::env::var_os("HOME")?).join(rest)),
+            Err(_) => Some(p.clone()),
+        },
+        None => Some(state::state_dir()?.join("audit.jsonl")),
+    }
+}
+
+/// Append a record to the configured log. Best effort: a log that can't be
+/// written never blocks a tool call.
+pub fn log(settings: &config::Audit, record: &Record) {
+    if !settings.enabled {
+        return;
+    }
+    if let Some(path) = log_path(settings) {
+        let _ = append(&path, record, settings.max_bytes, settings.keep);
+    }
+}
+
+/// Append a record even if the vault turned the audit log off.
+/// Break-glass overrides are always recorded, and fail if they can't be.
+pub fn log_always(settings: &config::Audit, record: &Record) -> io::Result<()> {
+    let path = log_path(settings)
+        .ok_or_else(|| io::Error::other("cannot determine the audit log location"))?;
+    append(&path, record, settings.max_bytes, settings.keep)
+        .map_err(|e| io::Error::new(e.kind(), format!("{}: {e}", path.display())))
+}
+
+/// Append a record as one JSON line, rotating first if the file would
+/// grow past `max_bytes`. Rotated files are `<path>.1` (newest) to `<path>.<keep>`.
+pub fn append(path: &Path, record: &Record, max_bytes: u64, keep: usize) -> io::Result<()> {
+    let mut line = serde_json::to_string(record).map_err(io::Error::other)?;
+    line.push('\n');
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let size = fs::metadata(path).map_or(0, |m| m.len());
+    if size > 0 && size + line.len() as u64 > max_bytes {
+        rotate(path, keep)?;
+    }
+
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    file.write_all(line.as_bytes())
+}
+
+fn rotated(path: &Path, n: usize) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(format!(".{n}"));
+    PathBuf::from(name)
+}
+
+fn rotate(path: &Path, keep: usize) -> io::Result<()> {
+    if keep == 0 {
+        return fs::remove_file(path);
+    }
+    for n in (1..keep).rev() {
+        let from = rotated(path, n);
+        if from.exists() {
+            fs::rename(&from, rotated(path, n + 1))?;
+        }
+    }
+    fs::rename(path, rotated(path, 1))
+}
+
+/// Read every record from the log and its rotations, oldest first.
+/// Lines that don't parse are skipped.
+pub fn read_all(path: &Path, keep: usize) -> Vec<Record> {
+    let mut files: Vec<PathBuf> = (1..=keep).rev().map(|n| rotated(path, n)).collect();
+    files.push(path.to_path_buf());
+
+    files
+        .iter()
+        .filter_map(|f| fs::read_to_string(f).ok())
+        .flat_map(|content| {
+            content
+                .lines()
+                .filter_map(|line| serde_json::from_str(line).ok())
+                .collect::<Vec<Record>>()
+        })
+        .collect()
+}
+
+/// Format seconds since the Unix epoch as an RFC 3339 UTC timestamp.
+pub fn rfc3339(secs: u64) -> String {
+    let days = secs / 86_400;
+    let rem = secs % 86_400;
+    let (year, month, day) = civil_from_days(days);
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z",
+        rem / 3600,
+        rem % 3600 / 60,
+        rem % 60
+    )
+}
+
+/// Convert days since 1970-01-01 to a (year, month, day) civil date.
+/// Howard Hinnant's algorithm, restricted to dates after the epoch.
+fn civil_from_days(days: u64) -> (u64, u64, u64) {
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let doe = z % 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + u64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,117 @@
+use super::*;
+use tempfile::tempdir;
+
+fn record(session: &str, rel_path: &str, decision: Decision) -> Record {
+    Record {
+        timestamp: rfc3339(0),
+        session_id: Some(session.to_string()),
+        tool: "Read".to_string(),
+        path: format!("/vault/{rel_path}"),
+        rel_path: rel_path.to_string(),
+        level: "AMBER".to_string(),
+        rule: "default".to_string(),
+        decision,
+        ..Record::default()
+    }
+}
+
+#[test]
+fn test_rfc3339_epoch() {
+    assert_eq!(rfc3339(0), "1970-01-01T00:00:00Z");
+}
+
+#[test]
+fn test_rfc3339_leap_day() {
+    assert_eq!(rfc3339(1_709_210_096), "2024-02-29T12:34:56Z");
+}
+
+#[test]
+fn test_append_and_read_back() {
+    let dir = tempdir().unwrap();
+    let log = dir.path().join("audit.jsonl");
+
+    append(&log, &record("s1", "a.md", Decision::Allow), 1 << 20, 3).unwrap();
+    append(&log, &record("s1", "b.md", Decision::Deny), 1 << 20, 3).unwrap();
+
+    let records = read_all(&log, 3);
+    assert_eq!(records.len(), 2);
+    assert_eq!(records[0].rel_path, "a.md");
+    assert_eq!(records[1].decision, Decision::Deny);
+}
+
+#[test]
+fn test_append_creates_parent_dirs() {
+    let dir = tempdir().unwrap();
+    let log = dir.path().join("nested/state/audit.jsonl");
+
+    append(&log, &record("s1", "a.md", Decision::Allow), 1 << 20, 3).unwrap();
+    assert!(log.exists());
+}
+
+#[test]
+fn test_rotation_keeps_order() {
+    let dir = tempdir().unwrap();
+    let log = dir.path().join("audit.jsonl");
+
+    // Tiny limit: every append after the first rotates
+    for name in ["1.md", "2.md", "3.md"] {
+        append(&log, &record("s1", name, Decision::Allow), 10, 5).unwrap();
+    }
+
+    assert!(dir.path().join("audit.jsonl.1").exists());
+    assert!(dir.path().join("audit.jsonl.2").exists());
+    let names: Vec<String> = read_all(&log, 5).into_iter().map(|r| r.rel_path).collect();
+    assert_eq!(names, ["1.md", "2.md", "3.md"]);
+}
+
+#[test]
+fn test_rotation_drops_beyond_keep() {
+    let dir = tempdir().unwrap();
+    let log = dir.path().join("audit.jsonl");
+
+    for name in ["1.md", "2.md", "3.md", "4.md"] {
+        append(&log, &record("s1", name, Decision::Allow), 10, 2).unwrap();
+    }
+
+    assert!(!dir.path().join("audit.jsonl.3").exists());
+    let names: Vec<String> = read_all(&log, 2).into_iter().map(|r| r.rel_path).collect();
+    assert_eq!(names, ["2.md", "3.md", "4.md"]);
+}
+
+#[test]
+fn test_read_skips_malformed_lines() {
+    let dir = tempdir().unwrap();
+    let log = dir.path().join("audit.jsonl");
+    fs::write(&log, "not json\n").unwrap();
+    append(&log, &record("s1", "a.md", Decision::Allow), 1 << 20, 3).unwrap();
+
+    assert_eq!(read_all(&log, 3).len(), 1);
+}
+
+#[test]
+fn test_filter_by_session_path_decision() {
+    let r = record("s1", "Journals/today.md", Decision::Deny);
+
+    assert!(Filter::default().matches(&r));
+    assert!(Filter {
+        session: Some("s1".into()),
+        ..Filter::default()
+    }
+    .matches(&r));
+    assert!(!Filter {
+        session: Some("s2".into()),
+        ..Filter::default()
+    }
+    .matches(&r));
+    assert!(Filter {
+        path: Some("Journals/".into()),
+        decision: Some(Decision::Deny),
+        ..Filter::default()
+    }
+    .matches(&r));
+    assert!(!Filter {
+        decision: Some(Decision::Allow),
+        ..Filter::default()
+    }
+    .matches(&r));
+}
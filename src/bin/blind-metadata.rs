@@ -1,4 +1,5 @@
 use context_tlp::frontmatter;
+use context_tlp::tamper;
 use context_tlp::vault;
 use std::env;
 use std::fs;
@@ -68,6 +69,10 @@ fn cmd_set(dir: &Path, key: &str, value: Option<&str>) -> ExitCode {
         };
 
         let new_content = frontmatter::set_value(&content, key, value);
+        if let Some(why) = tamper::weakening(&content, &new_content) {
+            eprintln!("  refused: {name} (the change {why})");
+            continue;
+        }
         if new_content == content {
             println!("  ok:      {name}");
             count += 1;
@@ -1,23 +1,92 @@
+use context_tlp::approval;
+use context_tlp::audit::{self, Decision};
+use context_tlp::config;
+use context_tlp::fingerprint;
 use context_tlp::redact;
+use context_tlp::state;
 use context_tlp::tlp;
 use std::env;
 use std::fs;
 use std::process::ExitCode;
 
+fn log(
+    file_path: &str,
+    c: &tlp::Classification,
+    decision: Decision,
+    secrets: usize,
+    redactions: usize,
+) {
+    let settings = config::load(&c.vault_root);
+    let mut record = audit::Record::new("safe-read", file_path, c, decision);
+    record.session_id = state::session_id();
+    record.secrets = secrets;
+    record.redactions = redactions;
+    record.monitor = decision == Decision::Deny && monitored(c);
+    audit::log(&settings.audit, &record);
+}
+
+/// Whether `.tlp` monitor mode lets a refused read through. It never covers
+/// RED files or a malformed config.
+fn monitored(c: &tlp::Classification) -> bool {
+    c.level != tlp::Tlp::Red && !c.config_error && config::load(&c.vault_root).monitoring()
+}
+
+/// Report a refusal. In monitor mode the read goes ahead (Ok), and is logged
+/// as a would-be denial once its redaction counts are known.
+fn refuse(file_path: &str, c: &tlp::Classification, reason: &str) -> Result<(), ExitCode> {
+    if monitored(c) {
+        let reason = reason.lines().next().unwrap_or_default();
+        eprintln!("TLP monitor mode — would deny, allowed: {reason}");
+        return Ok(());
+    }
+    log(file_path, c, Decision::Deny, 0, 0);
+    eprintln!("{reason}");
+    Err(ExitCode::from(1))
+}
+
 fn main() -> ExitCode {
     let args: Vec<String> = env::args().collect();
-    if args.len() != 2 {
-        eprintln!("Usage: safe-read <file-path>");
-        return ExitCode::from(1);
+    let mut token = env::var(approval::TOKEN_ENV).ok();
+    let mut paths = Vec::new();
+    let mut rest = args[1..].iter();
+    while let Some(arg) = rest.next() {
+        match arg.as_str() {
+            "--token" => token = rest.next().cloned(),
+            _ => paths.push(arg),
+        }
     }
+    let [file_path] = paths.as_slice() else {
+        eprintln!("Usage: safe-read [--token <token>] <file-path>");
+        return ExitCode::from(1);
+    };
 
-    let file_path = &args[1];
-
-    // Check TLP classification — refuse RED files
-    if let Some(c) = tlp::classify_file(file_path) {
-        if c.level == tlp::Tlp::Red {
-            eprintln!("TLP:RED — this file is blocked. safe-read only handles AMBER files.");
-            return ExitCode::from(1);
+    // Check TLP classification — refuse RED files, and AMBER files without approval
+    let classification = tlp::classify_file(file_path);
+    let refusal = classification.as_ref().and_then(|c| match c.level {
+        tlp::Tlp::Red => Some(match &c.diagnostic {
+            Some(diagnostic) => {
+                format!("TLP:RED — {diagnostic}. All files treated as RED until fixed.")
+            }
+            None => "TLP:RED — this file is blocked. safe-read only handles AMBER files.".into(),
+        }),
+        tlp::Tlp::Amber
+            if !approval::is_authorized(
+                file_path,
+                state::session_id().as_deref(),
+                token.as_deref(),
+            ) =>
+        {
+            Some(format!(
+                "TLP:AMBER — no valid approval for {}. Ask the user to run in their terminal:\n\
+                 tlp approve \"{file_path}\"",
+                c.rel_path
+            ))
+        }
+        _ => None,
+    });
+    if let (Some(c), Some(reason)) = (&classification, &refusal) {
+        if let Err(code) = refuse(file_path, c, reason) {
+            return code;
         }
     }
 
@@ -29,16 +98,48 @@ fn main() -> ExitCode {
         }
     };
 
-    let tlp_redacted = redact::redact_tlp_sections(&content);
-    let (output, secrets_found) = redact::redact_secrets(&tlp_redacted);
+    // Compartments are revealed by .tlp or by the user's grant, never per call
+    let mut opts = config::for_file(file_path).redaction;
+    opts.reveal.extend(approval::revealed(
+        file_path,
+        state::session_id().as_deref(),
+        token.as_deref(),
+    ));
+    let view = redact::safe_view_with(&content, &opts);
+
+    if let Some(c) = &classification {
+        // Reaching here with a refusal means monitor mode let the read through
+        let decision = if refusal.is_some() {
+            Decision::Deny
+        } else {
+            Decision::Allow
+        };
+        log(file_path, c, decision, view.secrets, view.redactions);
+        if matches!(c.level, tlp::Tlp::Red | tlp::Tlp::Amber) {
+            fingerprint::record_disclosure(state::session_id().as_deref(), &view.text);
+        }
+    }
 
-    if secrets_found {
+    if view.secrets > 0 {
         eprintln!(
             "⚠ WARNING: secret(s) detected and redacted in {file_path}. \
              Consider rotating the exposed key(s)."
         );
     }
 
-    print!("{output}");
+    if view.pii > 0 {
+        eprintln!("TLP: {} PII match(es) redacted in {file_path}", view.pii);
+    }
+
+    if !view.compartments.is_empty() {
+        let counts: Vec<String> = view
+            .compartments
+            .iter()
+            .map(|(name, n)| format!("{name}: {n}"))
+            .collect();
+        eprintln!("TLP compartments redacted — {}", counts.join(", "));
+    }
+
+    print!("{}", view.text);
     ExitCode::SUCCESS
 }
@@ -1,9 +1,16 @@
+use context_tlp::approval;
+use context_tlp::audit::{self, Decision};
+use context_tlp::config::{self, Redaction};
+use context_tlp::fingerprint;
 use context_tlp::redact;
+use context_tlp::state;
+use context_tlp::tamper;
 use context_tlp::tlp;
 use similar::{ChangeTag, TextDiff};
 use std::env;
 use std::fs;
 use std::io::{self, Read};
+use std::path::Path;
 use std::process::ExitCode;
 
 fn print_usage() {
@@ -14,12 +21,14 @@ fn print_usage() {
     eprintln!("  safe-write insert <file> --after <marker> --content <text>");
     eprintln!();
     eprintln!("Edit: replace exactly one occurrence of old-string with new-string.");
-    eprintln!("Write: overwrite entire file, preserving #tlp/red blocks and secrets.");
+    eprintln!("Write: overwrite entire file, preserving #tlp/red blocks, secrets and PII.");
     eprintln!("Insert: add text before or after a marker line (trimmed match).");
     eprintln!();
     eprintln!("Global flags (all modes):");
     eprintln!("  --quiet, -q    Suppress diff output on stderr");
     eprintln!("  --human, -H    Compact human-readable diff (default: unified diff)");
+    eprintln!("  --token <t>    Approval from `tlp approve` for AMBER files");
+    eprintln!("                 (or set TLP_APPROVAL_TOKEN)");
 }
 
 // ─── Diff output ───
@@ -83,11 +92,16 @@ fn main() -> ExitCode {
     let file_path = &args[2];
 
     // Extract global flags before mode dispatch
-    let remaining: Vec<String> = args[3..]
-        .iter()
-        .filter(|a| !matches!(a.as_str(), "--quiet" | "-q" | "--human" | "-H"))
-        .cloned()
-        .collect();
+    let mut remaining: Vec<String> = Vec::new();
+    let mut token = env::var(approval::TOKEN_ENV).ok();
+    let mut rest = args[3..].iter();
+    while let Some(arg) = rest.next() {
+        match arg.as_str() {
+            "--quiet" | "-q" | "--human" | "-H" => {}
+            "--token" => token = rest.next().cloned(),
+            _ => remaining.push(arg.clone()),
+        }
+    }
     let quiet = args[3..]
         .iter()
         .any(|a| matches!(a.as_str(), "--quiet" | "-q"));
@@ -95,16 +109,66 @@ fn main() -> ExitCode {
         .iter()
         .any(|a| matches!(a.as_str(), "--human" | "-H"));
 
-    // TLP gate: refuse RED files
+    if tamper::is_config(file_path) {
+        eprintln!("TLP — .tlp files are protected. Ask the user to change the policy.");
+        return ExitCode::from(1);
+    }
+
+    // TLP gate: refuse RED files, and existing AMBER files without approval
     if let Some(c) = tlp::classify_file(file_path) {
-        if c.level == tlp::Tlp::Red {
-            eprintln!("TLP:RED — safe-write refuses RED files.");
+        let settings = config::load(&c.vault_root);
+        let unapproved = c.level == tlp::Tlp::Amber
+            && Path::new(file_path).exists()
+            && !approval::is_authorized(
+                file_path,
+                state::session_id().as_deref(),
+                token.as_deref(),
+            );
+        let decision = if c.level == tlp::Tlp::Red || unapproved {
+            Decision::Deny
+        } else {
+            Decision::Allow
+        };
+        // Monitor mode never lets a RED file or a malformed config through
+        let monitor = decision == Decision::Deny
+            && c.level != tlp::Tlp::Red
+            && !c.config_error
+            && settings.monitoring();
+        let mut record = audit::Record::new(&format!("safe-write {mode}"), file_path, &c, decision);
+        record.session_id = state::session_id();
+        record.monitor = monitor;
+        audit::log(&settings.audit, &record);
+
+        let reason = if unapproved {
+            format!(
+                "TLP:AMBER — no valid approval for {}. Ask the user to run in their terminal:\n\
+                 tlp approve \"{file_path}\"",
+                c.rel_path
+            )
+        } else if let Some(diagnostic) = &c.diagnostic {
+            format!("TLP:RED — {diagnostic}. All files treated as RED until fixed.")
+        } else {
+            "TLP:RED — safe-write refuses RED files.".to_string()
+        };
+        if monitor {
+            let reason = reason.lines().next().unwrap_or_default();
+            eprintln!("TLP monitor mode — would deny, allowed: {reason}");
+        } else if decision == Decision::Deny {
+            eprintln!("{reason}");
             return ExitCode::from(1);
         }
     }
 
+    // The same view safe-read showed: .tlp reveals plus the user's grants
+    let mut opts = config::for_file(file_path).redaction;
+    opts.reveal.extend(approval::revealed(
+        file_path,
+        state::session_id().as_deref(),
+        token.as_deref(),
+    ));
+
     match mode.as_str() {
-        "edit" => cmd_edit(file_path, &remaining, quiet, human),
+        "edit" => cmd_edit(file_path, &remaining, &opts, quiet, human),
         "write" => {
             if !remaining.is_empty() {
                 eprintln!(
@@ -114,9 +178,9 @@ fn main() -> ExitCode {
                 );
                 return ExitCode::from(1);
             }
-            cmd_write(file_path, quiet, human)
+            cmd_write(file_path, &opts, quiet, human)
         }
-        "insert" => cmd_insert(file_path, &remaining, quiet, human),
+        "insert" => cmd_insert(file_path, &remaining, &opts, quiet, human),
         _ => {
             eprintln!("Unknown mode: {mode}");
             print_usage();
@@ -136,14 +200,64 @@ fn unescape_shell(s: &str) -> String {
     s.replace("\\!", "!")
 }
 
-// ─── Edit mode ───
+// ─── Leak check ───
 //
-// Operates on the ORIGINAL file content (not the safe-read view).
-// The AI constructs old_string from safe-read output, but non-redacted text
-// is identical between the original and safe-read view. So old_string matches
-// the original directly — as long as it doesn't span redacted content.
+// Text going into a GREEN/CLEAR file (or one outside any vault) must not
+// reproduce AMBER content that safe-read disclosed earlier in the session.
+// safe-write cannot prompt, so `ask` refuses too. Monitor mode only warns.
+
+fn check_leak(file_path: &str, text: &str) -> Result<(), ExitCode> {
+    let Some(leak) = fingerprint::check_write(file_path, state::session_id().as_deref(), text)
+    else {
+        return Ok(());
+    };
+    let monitor = tlp::classify_file(file_path)
+        .map_or_else(config::Settings::default, |c| config::load(&c.vault_root))
+        .monitoring();
+    let (prefix, result) = if monitor {
+        ("TLP monitor mode — would refuse, allowed", Ok(()))
+    } else {
+        ("TLP — refusing", Err(ExitCode::from(1)))
+    };
+    eprintln!(
+        "{prefix}: copying AMBER content read earlier in the session into a lower-level \
+         file ({} of {} word shingles match). Summarise at the target's level instead.",
+        leak.overlap.matched, leak.overlap.total
+    );
+    result
+}
 
-fn cmd_edit(file_path: &str, args: &[String], quiet: bool, human: bool) -> ExitCode {
+// ─── Tamper check ───
+//
+// Whatever the file's level, a write must not lower its `tlp` frontmatter,
+// drop `#tlp/red` markers or expose hidden sections. Checked on the final
+// text, after restoration.
+
+fn check_tamper(file_path: &str, old: &str, new: &str, opts: &Redaction) -> Result<(), ExitCode> {
+    let Some(why) = tamper::weakening_with(old, new, opts) else {
+        return Ok(());
+    };
+    eprintln!(
+        "TLP — refusing: this change {why} in {file_path}. Only the user may lower a \
+         classification."
+    );
+    Err(ExitCode::from(1))
+}
+
+// ─── Edit mode ───
+//
+// Operates on the safe-read view, not the original: old_string is matched
+// against visible text only, so a hit or miss says nothing about hidden
+// content, and the diff shows placeholders. The edited view is then restored
+// over the original by placeholder ID, as in write mode.
+
+fn cmd_edit(
+    file_path: &str,
+    args: &[String],
+    opts: &Redaction,
+    quiet: bool,
+    human: bool,
+) -> ExitCode {
     let (mut old_string, mut new_string) = (None, None);
     let mut i = 0;
     while i < args.len() {
@@ -180,7 +294,7 @@ fn cmd_edit(file_path: &str, args: &[String], quiet: bool, human: bool) -> ExitC
     let new = new_owned.as_str();
 
     // Guard: reject edits targeting redacted placeholders
-    if old.contains(redact::REDACTED_MARKER) || old.contains(redact::SECRET_MARKER) {
+    if redact::has_placeholders(old) {
         eprintln!(
             "old_string contains redaction markers — cannot edit hidden content. \
              Use safe-read to view what's visible, then edit only visible text."
@@ -188,6 +302,10 @@ fn cmd_edit(file_path: &str, args: &[String], quiet: bool, human: bool) -> ExitC
         return ExitCode::from(1);
     }
 
+    if let Err(code) = check_leak(file_path, new) {
+        return code;
+    }
+
     let content = match fs::read_to_string(file_path) {
         Ok(c) => c,
         Err(e) => {
@@ -196,8 +314,9 @@ fn cmd_edit(file_path: &str, args: &[String], quiet: bool, human: bool) -> ExitC
         }
     };
 
-    let count = content.matches(old).count();
-    match count {
+    let view = redact::safe_view_with(&content, opts).text;
+    let found: Vec<usize> = view.match_indices(old).map(|(at, _)| at).collect();
+    match found.len() {
         0 => {
             eprintln!("old_string not found in {file_path}");
             eprintln!(
@@ -207,13 +326,31 @@ fn cmd_edit(file_path: &str, args: &[String], quiet: bool, human: bool) -> ExitC
             ExitCode::from(1)
         }
         1 => {
-            let result = content.replacen(old, new, 1);
+            if redact::overlaps_placeholder(&view, found[0]..found[0] + old.len()) {
+                eprintln!(
+                    "old_string overlaps a redaction marker — cannot edit hidden content. \
+                     Edit only visible text."
+                );
+                return ExitCode::from(1);
+            }
+            let edited = view.replacen(old, new, 1);
+            let result = match redact::restore_view(&content, &edited, opts) {
+                Ok(merged) => merged,
+                Err(msg) => {
+                    eprintln!("Restoration failed: {msg}");
+                    eprintln!("The original file was NOT modified.");
+                    return ExitCode::from(1);
+                }
+            };
+            if let Err(code) = check_tamper(file_path, &content, &result, opts) {
+                return code;
+            }
             if let Err(e) = fs::write(file_path, &result) {
                 eprintln!("Cannot write {file_path}: {e}");
                 return ExitCode::from(1);
             }
             if !quiet {
-                emit_diff(&content, &result, file_path, human);
+                emit_diff(&view, &edited, file_path, human);
             }
             println!("{file_path}");
             ExitCode::SUCCESS
@@ -229,7 +366,8 @@ fn cmd_edit(file_path: &str, args: &[String], quiet: bool, human: bool) -> ExitC
 //
 // Line-based insertion: find a marker line (trimmed match) and insert content
 // before or after it. Resilient to whitespace changes — the marker only needs
-// to match after trimming both sides.
+// to match after trimming both sides. Like edit mode, markers are matched
+// against the safe-read view and the result restored over the original.
 
 fn parse_insert_args(args: &[String]) -> Result<(bool, &str, &str), ExitCode> {
     let (mut before, mut after, mut content) = (None, None, None);
@@ -273,7 +411,13 @@ fn parse_insert_args(args: &[String]) -> Result<(bool, &str, &str), ExitCode> {
     Ok((before.is_some(), marker, text))
 }
 
-fn cmd_insert(file_path: &str, args: &[String], quiet: bool, human: bool) -> ExitCode {
+fn cmd_insert(
+    file_path: &str,
+    args: &[String],
+    opts: &Redaction,
+    quiet: bool,
+    human: bool,
+) -> ExitCode {
     let (insert_before, marker_raw, text_raw) = match parse_insert_args(args) {
         Ok(v) => v,
         Err(code) => return code,
@@ -285,7 +429,7 @@ fn cmd_insert(file_path: &str, args: &[String], quiet: bool, human: bool) -> Exi
     let text = text_owned.as_str();
 
     // Guard: reject content containing redaction markers
-    if text.contains(redact::REDACTED_MARKER) || text.contains(redact::SECRET_MARKER) {
+    if redact::has_placeholders(text) {
         eprintln!(
             "content contains redaction markers — cannot insert hidden content. \
              Use safe-read to view what's visible, then insert only visible text."
@@ -293,6 +437,10 @@ fn cmd_insert(file_path: &str, args: &[String], quiet: bool, human: bool) -> Exi
         return ExitCode::from(1);
     }
 
+    if let Err(code) = check_leak(file_path, text) {
+        return code;
+    }
+
     let file_content = match fs::read_to_string(file_path) {
         Ok(c) => c,
         Err(e) => {
@@ -301,7 +449,8 @@ fn cmd_insert(file_path: &str, args: &[String], quiet: bool, human: bool) -> Exi
         }
     };
 
-    let lines: Vec<&str> = file_content.lines().collect();
+    let view = redact::safe_view_with(&file_content, opts).text;
+    let lines: Vec<&str> = view.lines().collect();
     let trimmed_marker = marker.trim();
     let matches: Vec<usize> = lines
         .iter()
@@ -328,16 +477,27 @@ fn cmd_insert(file_path: &str, args: &[String], quiet: bool, human: bool) -> Exi
                 }
             }
             // Preserve trailing newline if original had one
-            let mut output = result.join("\n");
-            if file_content.ends_with('\n') {
-                output.push('\n');
+            let mut edited = result.join("\n");
+            if view.ends_with('\n') {
+                edited.push('\n');
+            }
+            let output = match redact::restore_view(&file_content, &edited, opts) {
+                Ok(merged) => merged,
+                Err(msg) => {
+                    eprintln!("Restoration failed: {msg}");
+                    eprintln!("The original file was NOT modified.");
+                    return ExitCode::from(1);
+                }
+            };
+            if let Err(code) = check_tamper(file_path, &file_content, &output, opts) {
+                return code;
             }
             if let Err(e) = fs::write(file_path, &output) {
                 eprintln!("Cannot write {file_path}: {e}");
                 return ExitCode::from(1);
             }
             if !quiet {
-                emit_diff(&file_content, &output, file_path, human);
+                emit_diff(&view, &edited, file_path, human);
             }
             println!("{file_path}");
             ExitCode::SUCCESS
@@ -351,9 +511,11 @@ fn cmd_insert(file_path: &str, args: &[String], quiet: bool, human: bool) -> Exi
 
 // ─── Write mode ───
 //
-// The AI sends content based on the safe-read view, which has [REDACTED] and
-// [SECRET REDACTED] markers where hidden content was stripped. We must restore
-// those markers with the original hidden content before writing to disk.
+// The AI sends content based on the safe-read view, which has [REDACTED#n] and
+// [SECRET REDACTED#n] placeholders where hidden content was stripped. We must
+// restore them with the original hidden content before writing to disk.
+// Numbered placeholders are restored by ID, so sections may move; unnumbered
+// ones (from older safe-read output) are restored in order.
 //
 // Pipeline:
 //   1. Read original from disk
@@ -362,7 +524,7 @@ fn cmd_insert(file_path: &str, args: &[String], quiet: bool, human: bool) -> Exi
 //   4. Replace markers in new content with original hidden chunks
 //   5. Write the merged result to disk
 
-fn cmd_write(file_path: &str, quiet: bool, human: bool) -> ExitCode {
+fn cmd_write(file_path: &str, opts: &Redaction, quiet: bool, human: bool) -> ExitCode {
     let mut new_content = String::new();
     if let Err(e) = io::stdin().read_to_string(&mut new_content) {
         eprintln!("Cannot read stdin: {e}");
@@ -374,6 +536,10 @@ fn cmd_write(file_path: &str, quiet: bool, human: bool) -> ExitCode {
         return ExitCode::from(1);
     }
 
+    if let Err(code) = check_leak(file_path, &new_content) {
+        return code;
+    }
+
     // Read original to extract hidden content
     let original = match fs::read_to_string(file_path) {
         Ok(c) => c,
@@ -393,24 +559,30 @@ fn cmd_write(file_path: &str, quiet: bool, human: bool) -> ExitCode {
     };
 
     // Extract hidden content from original (same pipeline order as safe-read)
-    let tlp_blocks = redact::extract_tlp_blocks(&original);
-    let inline_chunks = redact::extract_inline_tlp_chunks(&original);
-    let tlp_redacted = redact::redact_tlp_sections(&original);
+    let tlp_blocks = redact::extract_tlp_blocks_with(&original, opts);
+    let inline_chunks = redact::extract_inline_tlp_chunks_with(&original, opts);
+    let tlp_redacted = redact::redact_tlp_sections_with(&original, opts);
     let secrets = redact::extract_secret_matches(&tlp_redacted);
+    let (secret_redacted, _) = redact::redact_secrets(&tlp_redacted);
+    let pii = redact::extract_pii_matches(&secret_redacted, opts);
 
-    let has_hidden = !tlp_blocks.is_empty() || !inline_chunks.is_empty() || !secrets.is_empty();
+    let has_hidden = !tlp_blocks.is_empty()
+        || !inline_chunks.is_empty()
+        || !secrets.is_empty()
+        || !pii.is_empty();
 
     if !has_hidden {
         // No hidden content — but check for spurious markers in new content
-        let has_markers = new_content.contains(redact::REDACTED_MARKER)
-            || new_content.contains(redact::SECRET_MARKER);
-        if has_markers {
+        if redact::has_placeholders(&new_content) {
             eprintln!(
                 "New content contains redaction markers but the original file has no \
                  hidden content to restore. This would write literal marker text."
             );
             return ExitCode::from(1);
         }
+        if let Err(code) = check_tamper(file_path, &original, &new_content, opts) {
+            return code;
+        }
         if let Err(e) = fs::write(file_path, &new_content) {
             eprintln!("Cannot write {file_path}: {e}");
             return ExitCode::from(1);
@@ -422,27 +594,41 @@ fn cmd_write(file_path: &str, quiet: bool, human: bool) -> ExitCode {
         return ExitCode::SUCCESS;
     }
 
-    // Restore hidden content into the new text
-    match redact::restore_hidden(&new_content, &tlp_blocks, &inline_chunks, &secrets) {
+    if let Err(msg) = redact::check_no_literal_placeholders(&original) {
+        eprintln!("Restoration failed: {msg}");
+        eprintln!("The original file was NOT modified.");
+        return ExitCode::from(1);
+    }
+
+    // Restore hidden content into the new text: by ID for the numbered
+    // placeholders safe-read emits, by order for legacy unnumbered ones.
+    // Personal data was never shown unnumbered, so it always goes by ID.
+    let restored = if redact::has_numbered(&new_content) || !pii.is_empty() {
+        let spans = redact::extract_tlp_spans_with(&original, opts);
+        redact::restore_numbered(&new_content, &spans, &secrets, &pii)
+    } else {
+        redact::restore_hidden(&new_content, &tlp_blocks, &inline_chunks, &secrets)
+    };
+    match restored {
         Ok(merged) => {
+            if let Err(code) = check_tamper(file_path, &original, &merged, opts) {
+                return code;
+            }
             if let Err(e) = fs::write(file_path, &merged) {
                 eprintln!("Cannot write {file_path}: {e}");
                 return ExitCode::from(1);
             }
             if !quiet {
                 // Diff the safe-read view (with [REDACTED] markers), not raw secrets
-                let safe_view = {
-                    let tlp_redacted = redact::redact_tlp_sections(&original);
-                    let (view, _) = redact::redact_secrets(&tlp_redacted);
-                    view
-                };
-                emit_diff(&safe_view, &new_content, file_path, human);
+                let safe_view = redact::safe_view_with(&original, opts);
+                emit_diff(&safe_view.text, &new_content, file_path, human);
             }
             eprintln!(
-                "Restored {} TLP block(s), {} inline chunk(s), {} secret(s)",
+                "Restored {} TLP block(s), {} inline chunk(s), {} secret(s), {} PII match(es)",
                 tlp_blocks.len(),
                 inline_chunks.len(),
-                secrets.len()
+                secrets.len(),
+                pii.len()
             );
             println!("{file_path}");
             ExitCode::SUCCESS
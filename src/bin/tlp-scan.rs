@@ -0,0 +1,184 @@
+use context_tlp::approval;
+use context_tlp::config::{self, ScanAction};
+use context_tlp::redact;
+use context_tlp::vault;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::io::Read;
+use std::path::Path;
+use std::process::ExitCode;
+
+/// JSON payload from Claude Code's `PostToolUse` hook.
+#[derive(Deserialize)]
+struct HookInput {
+    session_id: Option<String>,
+    tool_name: Option<String>,
+    tool_input: Option<ToolInput>,
+    tool_response: Option<Value>,
+    cwd: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ToolInput {
+    file_path: Option<String>,
+    /// Grep, Glob
+    path: Option<String>,
+}
+
+impl ToolInput {
+    /// Files or directories the tool call names, made absolute against `cwd`.
+    fn paths(&self, cwd: Option<&str>) -> Vec<String> {
+        [&self.file_path, &self.path]
+            .into_iter()
+            .flatten()
+            .map(|p| match cwd {
+                Some(cwd) => Path::new(cwd).join(p).to_string_lossy().to_string(),
+                None => p.clone(),
+            })
+            .collect()
+    }
+}
+
+/// Hidden content found in a tool response.
+#[derive(Default)]
+struct Findings {
+    tlp_sections: usize,
+    secrets: usize,
+    pii: usize,
+}
+
+impl Findings {
+    fn is_empty(&self) -> bool {
+        self.tlp_sections == 0 && self.secrets == 0 && self.pii == 0
+    }
+
+    fn describe(&self) -> String {
+        let mut parts = Vec::new();
+        if self.tlp_sections > 0 {
+            parts.push(format!("{} #tlp/red section(s)", self.tlp_sections));
+        }
+        if self.secrets > 0 {
+            parts.push(format!("{} secret(s)", self.secrets));
+        }
+        if self.pii > 0 {
+            parts.push(format!("{} PII match(es)", self.pii));
+        }
+        parts.join(" and ")
+    }
+}
+
+/// Redact every string in a JSON value in place, tallying what was hidden.
+fn redact_value(value: &mut Value, opts: &config::Redaction, findings: &mut Findings) {
+    match value {
+        Value::String(s) => {
+            let view = redact::safe_view_with(s, opts);
+            findings.tlp_sections += view.redactions;
+            findings.secrets += view.secrets;
+            findings.pii += view.pii;
+            *s = view.text;
+        }
+        Value::Array(items) => {
+            for item in items {
+                redact_value(item, opts, findings);
+            }
+        }
+        Value::Object(map) => {
+            for item in map.values_mut() {
+                redact_value(item, opts, findings);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Render a redacted tool response as text for the model.
+fn render(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => serde_json::to_string_pretty(other).unwrap_or_default(),
+    }
+}
+
+fn main() -> ExitCode {
+    let mut buf = String::new();
+    if std::io::stdin().read_to_string(&mut buf).is_err() {
+        return ExitCode::SUCCESS;
+    }
+
+    let Ok(input) = serde_json::from_str::<HookInput>(&buf) else {
+        return ExitCode::SUCCESS; // JSON parse error is Claude Code's responsibility
+    };
+
+    let tool_name = input.tool_name.unwrap_or_default();
+    let Some(mut response) = input.tool_response else {
+        return ExitCode::SUCCESS; // Nothing to scan
+    };
+
+    let vault_root = match input.cwd.as_deref() {
+        Some(cwd) => vault::find_vault_from_dir(Path::new(cwd)),
+        None => vault::find_vault_from_cwd(),
+    };
+    let Some(vault_root) = vault_root else {
+        return ExitCode::SUCCESS; // Session outside any vault — not our problem
+    };
+
+    let settings = config::load(&vault_root);
+    // Compartments the user revealed for the files involved stay visible, as in safe-read
+    let mut opts = settings.redaction.clone();
+    for path in input
+        .tool_input
+        .map(|t| t.paths(input.cwd.as_deref()))
+        .unwrap_or_default()
+    {
+        opts.reveal
+            .extend(approval::revealed(&path, input.session_id.as_deref(), None));
+    }
+    let mut findings = Findings::default();
+    redact_value(&mut response, &opts, &mut findings);
+    if findings.is_empty() {
+        return ExitCode::SUCCESS;
+    }
+
+    let summary = format!("{tool_name} output contained {}", findings.describe());
+
+    let output = match settings.tool_output.action_for(&tool_name) {
+        ScanAction::Block => json!({
+            "decision": "block",
+            "reason": format!(
+                "TLP — {summary} and was withheld. Do not repeat or act on its content."
+            ),
+            "systemMessage": format!("TLP — blocked {summary}."),
+        }),
+        ScanAction::Redact => {
+            let mut output = json!({
+                "decision": "block",
+                "reason": format!(
+                    "TLP — {summary}. Disregard the original result and use this redacted \
+                     version instead:\n\n{}",
+                    render(&response)
+                ),
+                "systemMessage": format!("TLP — redacted {summary}."),
+            });
+            // MCP tool results can be replaced outright
+            if tool_name.starts_with("mcp__") {
+                output["hookSpecificOutput"] = json!({
+                    "hookEventName": "PostToolUse",
+                    "updatedMCPToolOutput": response,
+                });
+            }
+            output
+        }
+        ScanAction::Warn => json!({
+            "hookSpecificOutput": {
+                "hookEventName": "PostToolUse",
+                "additionalContext": format!(
+                    "TLP — {summary}. Never output that content verbatim."
+                ),
+            },
+            "systemMessage": format!("TLP — {summary}."),
+        }),
+    };
+
+    println!("{output}");
+    ExitCode::SUCCESS
+}
@@ -0,0 +1,689 @@
+use context_tlp::approval::{self, Grant};
+use context_tlp::audit::{self, Decision};
+use context_tlp::breakglass::{self, Override};
+use context_tlp::config;
+use context_tlp::pin;
+use context_tlp::state;
+use context_tlp::tlp;
+use context_tlp::vault;
+use serde::Deserialize;
+use std::env;
+use std::fmt::Write as _;
+use std::fs::{self, OpenOptions};
+use std::io::{self, IsTerminal, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+fn print_usage() {
+    eprintln!("Usage:");
+    eprintln!(
+        "  tlp audit [--session <id>] [--path <substring>] [--decision allow|deny|redact|ask]"
+    );
+    eprintln!("            [--log <file>] [--json]");
+    eprintln!("  tlp approve <path|dir|glob> [--minutes <n>] [--reveal <compartments>]");
+    eprintln!("  tlp allow <path|dir|glob> [--minutes <n>] [--session <id>] [--reveal <c>]");
+    eprintln!("  tlp break-glass <file> [--minutes <n>]");
+    eprintln!("  tlp approvals");
+    eprintln!("  tlp revoke <path|dir|glob> | --all");
+    eprintln!("  tlp pin [dir]                   (defaults to the vault around the cwd)");
+    eprintln!("  tlp daemon                      (serves tlp-guard from a Unix socket)");
+    eprintln!("  tlp session-start               (reads SessionStart hook JSON from stdin)");
+    eprintln!();
+    eprintln!("Audit: list guard decisions, oldest first. --json prints raw JSON Lines.");
+    eprintln!("Approve: issue a signed token letting safe-read/safe-write open matching");
+    eprintln!("         AMBER files for --minutes (default {APPROVE_MINUTES}).");
+    eprintln!("Allow: let the agent read matching AMBER files (redacted) without asking.");
+    eprintln!("       Defaults to the current session; --minutes sets an expiry.");
+    eprintln!("--reveal: also show these #tlp/red/<compartment> sections (comma-separated)");
+    eprintln!("          in the files the grant covers.");
+    eprintln!("Break-glass: let the agent past the guard for one file, once (or for");
+    eprintln!("             --minutes). Asks for a reason; always recorded in the audit log.");
+    eprintln!("Pin: record the hash of every .tlp under a vault. If one changes, every");
+    eprintln!("     file in the vault is treated as RED until the user pins again.");
+    eprintln!("Approve, allow, break-glass and pin must be run by a human in an interactive");
+    eprintln!("terminal. This keeps a cooperating agent honest; it is not a security boundary.");
+    eprintln!("Daemon: keep vault roots and parsed .tlp configs cached for tlp-guard.");
+    eprintln!("Session-start: export the session id to CLI tools via CLAUDE_ENV_FILE.");
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().collect();
+    if args.len() < 2 {
+        print_usage();
+        return ExitCode::from(1);
+    }
+
+    match args[1].as_str() {
+        "audit" => cmd_audit(&args[2..]),
+        "approve" => cmd_approve(&args[2..]),
+        "allow" => cmd_allow(&args[2..]),
+        "break-glass" => cmd_break_glass(&args[2..]),
+        "approvals" => cmd_approvals(),
+        "revoke" => cmd_revoke(&args[2..]),
+        "pin" => cmd_pin(&args[2..]),
+        "daemon" => cmd_daemon(),
+        "session-start" => cmd_session_start(),
+        other => {
+            eprintln!("Unknown command: {other}");
+            print_usage();
+            ExitCode::from(1)
+        }
+    }
+}
+
+// ─── Audit ───
+//
+// Reads the log configured by the vault around the working directory
+// (or the default state-directory log), including rotated files.
+
+fn cmd_audit(args: &[String]) -> ExitCode {
+    let mut filter = audit::Filter::default();
+    let mut log_override = None;
+    let mut json = false;
+
+    let mut i = 0;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--session" => {
+                i += 1;
+                filter.session = args.get(i).cloned();
+            }
+            "--path" => {
+                i += 1;
+                filter.path = args.get(i).cloned();
+            }
+            "--decision" => {
+                i += 1;
+                let value = args.get(i).map_or("", String::as_str);
+                let Some(decision) = Decision::parse(value) else {
+                    eprintln!("Unknown decision: {value}");
+                    return ExitCode::from(1);
+                };
+                filter.decision = Some(decision);
+            }
+            "--log" => {
+                i += 1;
+                log_override = args.get(i).map(PathBuf::from);
+            }
+            "--json" => json = true,
+            other => {
+                eprintln!("Unknown flag: {other}");
+                return ExitCode::from(1);
+            }
+        }
+        i += 1;
+    }
+
+    let settings = vault::find_vault_from_cwd()
+        .map(|root| config::load(&root))
+        .unwrap_or_default();
+    let Some(path) = log_override.or_else(|| audit::log_path(&settings.audit)) else {
+        eprintln!("Cannot determine the audit log location (set XDG_STATE_HOME or HOME)");
+        return ExitCode::from(1);
+    };
+
+    for record in audit::read_all(&path, settings.audit.keep) {
+        if !filter.matches(&record) {
+            continue;
+        }
+        if json {
+            println!("{}", serde_json::to_string(&record).unwrap_or_default());
+            continue;
+        }
+        let mut line = format!(
+            "{} {:5} {:<16} {:5} {} ({})",
+            record.timestamp,
+            record.decision.as_str(),
+            record.tool,
+            record.level,
+            record.rel_path,
+            record.rule
+        );
+        if record.redactions > 0 || record.secrets > 0 {
+            let _ = write!(
+                line,
+                " [{} redaction(s), {} secret(s)]",
+                record.redactions, record.secrets
+            );
+        }
+        if record.monitor {
+            line.push_str(" [monitor]");
+        }
+        if let Some(reason) = &record.break_glass {
+            let _ = write!(line, " [break-glass: {reason}]");
+        }
+        if let Some(session) = &record.session_id {
+            let _ = write!(line, " session={session}");
+        }
+        println!("{line}");
+    }
+    ExitCode::SUCCESS
+}
+
+// ─── AMBER approvals ───
+//
+// Meant for the human, not the agent: grants are signed with a key kept in
+// the state directory, which tlp-guard keeps away from the file tools, and
+// minting one wants a terminal on stdin. Neither stops an agent with Bash
+// running as the same user — see "This is not a security boundary" in the
+// README.
+
+/// Default lifetime of a `tlp approve` token.
+const APPROVE_MINUTES: u64 = 15;
+
+fn cmd_approve(args: &[String]) -> ExitCode {
+    let mut target = None;
+    let mut minutes = APPROVE_MINUTES;
+    let mut reveal = Vec::new();
+
+    let mut i = 0;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--minutes" => {
+                i += 1;
+                let Some(n) = args.get(i).and_then(|v| v.parse::<u64>().ok()) else {
+                    eprintln!("--minutes requires a whole number");
+                    return ExitCode::from(1);
+                };
+                minutes = n;
+            }
+            "--reveal" => {
+                i += 1;
+                reveal.extend(args.get(i).map_or_else(Vec::new, |list| compartments(list)));
+            }
+            other if other.starts_with('-') => {
+                eprintln!("Unknown flag: {other}");
+                return ExitCode::from(1);
+            }
+            other => target = Some(other.to_string()),
+        }
+        i += 1;
+    }
+
+    let Some(target) = target else {
+        eprintln!("approve requires a path, directory or glob");
+        return ExitCode::from(1);
+    };
+
+    let now = state::now();
+    let grant = match issue(&target, None, Some(now + minutes * 60), reveal) {
+        Ok(g) => g,
+        Err(code) => return code,
+    };
+
+    eprintln!("Approved AMBER access to {}", describe(&grant));
+    println!("{}", grant.token());
+    ExitCode::SUCCESS
+}
+
+fn cmd_allow(args: &[String]) -> ExitCode {
+    let mut target = None;
+    let mut minutes = None;
+    let mut session = None;
+    let mut reveal = Vec::new();
+
+    let mut i = 0;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--minutes" => {
+                i += 1;
+                let Some(n) = args.get(i).and_then(|v| v.parse::<u64>().ok()) else {
+                    eprintln!("--minutes requires a whole number");
+                    return ExitCode::from(1);
+                };
+                minutes = Some(n);
+            }
+            "--session" => {
+                i += 1;
+                session = args.get(i).cloned();
+            }
+            "--reveal" => {
+                i += 1;
+                reveal.extend(args.get(i).map_or_else(Vec::new, |list| compartments(list)));
+            }
+            other if other.starts_with('-') => {
+                eprintln!("Unknown flag: {other}");
+                return ExitCode::from(1);
+            }
+            other => target = Some(other.to_string()),
+        }
+        i += 1;
+    }
+
+    let Some(target) = target else {
+        eprintln!("allow requires a path, directory or glob");
+        return ExitCode::from(1);
+    };
+
+    // No scope given: tie the grant to the session that is running now
+    if minutes.is_none() && session.is_none() {
+        session = approval::session_path()
+            .and_then(|p| fs::read_to_string(p).ok())
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty());
+        if session.is_none() {
+            eprintln!("No active session recorded. Use --minutes <n> or --session <id>.");
+            return ExitCode::from(1);
+        }
+    }
+
+    let now = state::now();
+    let grant = match issue(&target, session, minutes.map(|m| now + m * 60), reveal) {
+        Ok(g) => g,
+        Err(code) => return code,
+    };
+
+    println!("Approved AMBER reads of {}", describe(&grant));
+    ExitCode::SUCCESS
+}
+
+/// Compartment names from a comma-separated `--reveal` list.
+fn compartments(list: &str) -> Vec<String> {
+    let names = list.split(',').map(str::trim).filter(|n| !n.is_empty());
+    names.map(String::from).collect()
+}
+
+/// Sign and record a grant. Refuses unless stdin is a terminal, which keeps
+/// an agent from approving itself by accident, not on purpose.
+fn issue(
+    target: &str,
+    session: Option<String>,
+    expires: Option<u64>,
+    reveal: Vec<String>,
+) -> Result<Grant, ExitCode> {
+    if !io::stdin().is_terminal() {
+        eprintln!("Approvals must be issued by a human in an interactive terminal.");
+        return Err(ExitCode::from(1));
+    }
+
+    let pattern = approval::pattern_for(target).map_err(|e| {
+        eprintln!("Cannot resolve {target}: {e}");
+        ExitCode::from(1)
+    })?;
+
+    let (Some(path), Some(key_path)) = (approval::grants_path(), approval::key_path()) else {
+        eprintln!("Cannot determine the state directory (set XDG_STATE_HOME or HOME)");
+        return Err(ExitCode::from(1));
+    };
+    let key = approval::load_or_create_key(&key_path).map_err(|e| {
+        eprintln!("Cannot create {}: {e}", key_path.display());
+        ExitCode::from(1)
+    })?;
+
+    let now = state::now();
+    let mut grant = Grant {
+        pattern,
+        session_id: session,
+        expires,
+        granted: now,
+        reveal,
+        mac: String::new(),
+    };
+    grant.sign(&key);
+
+    let mut grants = approval::load(&path);
+    grants.push(grant.clone());
+    approval::save(&path, &grants, now).map_err(|e| {
+        eprintln!("Cannot write {}: {e}", path.display());
+        ExitCode::from(1)
+    })?;
+    Ok(grant)
+}
+
+fn cmd_approvals() -> ExitCode {
+    let (Some(path), Some(overrides_path)) =
+        (approval::grants_path(), breakglass::overrides_path())
+    else {
+        eprintln!("Cannot determine the state directory (set XDG_STATE_HOME or HOME)");
+        return ExitCode::from(1);
+    };
+    let now = state::now();
+    for grant in approval::load(&path) {
+        if grant.expires.is_none_or(|t| now < t) {
+            println!("{}", describe(&grant));
+        }
+    }
+    for o in breakglass::load(&overrides_path) {
+        if now < o.expires {
+            println!("{}", describe_override(&o));
+        }
+    }
+    ExitCode::SUCCESS
+}
+
+fn cmd_revoke(args: &[String]) -> ExitCode {
+    let Some(target) = args.first() else {
+        eprintln!("revoke requires a path, directory, glob, or --all");
+        return ExitCode::from(1);
+    };
+    let Some(path) = approval::grants_path() else {
+        eprintln!("Cannot determine the state directory (set XDG_STATE_HOME or HOME)");
+        return ExitCode::from(1);
+    };
+
+    let mut grants = approval::load(&path);
+    let before = grants.len();
+    if target == "--all" {
+        grants.clear();
+    } else {
+        let pattern = match approval::pattern_for(target) {
+            Ok(p) => p,
+            Err(e) => {
+                eprintln!("Cannot resolve {target}: {e}");
+                return ExitCode::from(1);
+            }
+        };
+        grants.retain(|g| g.pattern != pattern);
+    }
+
+    if let Err(e) = approval::save(&path, &grants, state::now()) {
+        eprintln!("Cannot write {}: {e}", path.display());
+        return ExitCode::from(1);
+    }
+    println!("Revoked {} approval(s)", before - grants.len());
+
+    // Break-glass overrides name single files; revoke those the target covers
+    let Some(overrides_path) = breakglass::overrides_path() else {
+        return ExitCode::SUCCESS;
+    };
+    let mut overrides = breakglass::load(&overrides_path);
+    let before = overrides.len();
+    if target == "--all" {
+        overrides.clear();
+    } else if let Ok(pattern) = approval::pattern_for(target) {
+        overrides.retain(|o| !tlp::matches_pattern(&o.path, &pattern));
+    }
+    if overrides.len() < before {
+        if let Err(e) = breakglass::save(&overrides_path, &overrides, state::now()) {
+            eprintln!("Cannot write {}: {e}", overrides_path.display());
+            return ExitCode::from(1);
+        }
+        println!(
+            "Revoked {} break-glass override(s)",
+            before - overrides.len()
+        );
+    }
+    ExitCode::SUCCESS
+}
+
+fn describe(grant: &Grant) -> String {
+    let mut line = grant.pattern.clone();
+    if let Some(session) = &grant.session_id {
+        let _ = write!(line, " for session {session}");
+    }
+    if let Some(expires) = grant.expires {
+        let _ = write!(line, " until {}", audit::rfc3339(expires));
+    }
+    if !grant.reveal.is_empty() {
+        let _ = write!(line, ", revealing {}", grant.reveal.join(", "));
+    }
+    line
+}
+
+fn describe_override(o: &Override) -> String {
+    let scope = if o.one_shot { "once" } else { "repeatedly" };
+    format!(
+        "{} break-glass ({scope}) until {}: {}",
+        o.path,
+        audit::rfc3339(o.expires),
+        o.reason
+    )
+}
+
+// ─── Break-glass ───
+//
+// Lets the agent past tlp-guard for one file, e.g. to fix broken YAML in a
+// RED note, without weakening .tlp. The user types a reason; issuing the
+// override and every use of it are recorded in the audit log, even if the
+// vault turned the log off.
+
+fn cmd_break_glass(args: &[String]) -> ExitCode {
+    let mut target = None;
+    let mut minutes = None;
+
+    let mut i = 0;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--minutes" => {
+                i += 1;
+                let Some(n) = args.get(i).and_then(|v| v.parse::<u64>().ok()) else {
+                    eprintln!("--minutes requires a whole number");
+                    return ExitCode::from(1);
+                };
+                minutes = Some(n);
+            }
+            other if other.starts_with('-') => {
+                eprintln!("Unknown flag: {other}");
+                return ExitCode::from(1);
+            }
+            other => target = Some(other.to_string()),
+        }
+        i += 1;
+    }
+
+    let Some(target) = target else {
+        eprintln!("break-glass requires a file path");
+        return ExitCode::from(1);
+    };
+    if !io::stdin().is_terminal() {
+        eprintln!("Break-glass overrides must be issued by a human in an interactive terminal.");
+        return ExitCode::from(1);
+    }
+    if target.contains('*') || Path::new(&target).is_dir() {
+        eprintln!("break-glass applies to a single file, not {target}");
+        return ExitCode::from(1);
+    }
+    let abs = match std::path::absolute(&target) {
+        Ok(p) => p.to_string_lossy().to_string(),
+        Err(e) => {
+            eprintln!("Cannot resolve {target}: {e}");
+            return ExitCode::from(1);
+        }
+    };
+    let Some(c) = tlp::classify_file(&abs) else {
+        eprintln!("{abs} is not inside a TLP vault; the guard does not restrict it");
+        return ExitCode::from(1);
+    };
+
+    eprint!(
+        "Reason for overriding TLP:{} on {}: ",
+        c.level.as_str(),
+        c.rel_path
+    );
+    let mut reason = String::new();
+    let _ = io::stdin().read_line(&mut reason);
+    let reason = reason.trim().to_string();
+    if reason.is_empty() {
+        eprintln!("A reason is required.");
+        return ExitCode::from(1);
+    }
+
+    let (Some(path), Some(key_path)) = (breakglass::overrides_path(), approval::key_path()) else {
+        eprintln!("Cannot determine the state directory (set XDG_STATE_HOME or HOME)");
+        return ExitCode::from(1);
+    };
+    let key = match approval::load_or_create_key(&key_path) {
+        Ok(k) => k,
+        Err(e) => {
+            eprintln!("Cannot create {}: {e}", key_path.display());
+            return ExitCode::from(1);
+        }
+    };
+
+    let now = state::now();
+    let mut o = Override {
+        path: abs.clone(),
+        reason: reason.clone(),
+        granted: now,
+        expires: now + minutes.unwrap_or(breakglass::ONE_SHOT_MINUTES) * 60,
+        one_shot: minutes.is_none(),
+        mac: String::new(),
+    };
+    o.sign(&key);
+
+    // Record it first: an override the log can't show is not granted
+    let mut record = audit::Record::new("tlp break-glass", &abs, &c, Decision::Allow);
+    record.break_glass = Some(reason);
+    if let Err(e) = audit::log_always(&config::load(&c.vault_root).audit, &record) {
+        eprintln!("Cannot write the audit log ({e}), so no override was granted");
+        return ExitCode::from(1);
+    }
+
+    let mut overrides = breakglass::load(&path);
+    overrides.push(o.clone());
+    if let Err(e) = breakglass::save(&path, &overrides, now) {
+        eprintln!("Cannot write {}: {e}", path.display());
+        return ExitCode::from(1);
+    }
+
+    println!("BREAK-GLASS: {}", describe_override(&o));
+    ExitCode::SUCCESS
+}
+
+// ─── Pin ───
+//
+// Hashes of the accepted .tlp files live in the state directory, so an
+// agent that rewrites or deletes a .tlp through its file tools can't also
+// update the record. Through Bash it could; pins are not a security boundary.
+
+fn cmd_pin(args: &[String]) -> ExitCode {
+    if !io::stdin().is_terminal() {
+        eprintln!("tlp pin must be run by a human in an interactive terminal.");
+        return ExitCode::from(1);
+    }
+    let root = match args {
+        [] => vault::find_vault_from_cwd(),
+        [dir] => std::path::absolute(dir).ok(),
+        _ => {
+            print_usage();
+            return ExitCode::from(1);
+        }
+    };
+    let Some(root) = root.filter(|r| r.is_dir()) else {
+        eprintln!("No vault to pin: pass a directory, or run inside a vault");
+        return ExitCode::from(1);
+    };
+
+    let scanned = match pin::scan(&root) {
+        Ok(s) if s.is_empty() => {
+            eprintln!("No .tlp files under {}", root.display());
+            return ExitCode::from(1);
+        }
+        Ok(s) => s,
+        Err(e) => {
+            eprintln!("Cannot scan {}: {e}", root.display());
+            return ExitCode::from(1);
+        }
+    };
+    let Some(path) = pin::pins_path() else {
+        eprintln!("Cannot determine the state directory (set XDG_STATE_HOME or HOME)");
+        return ExitCode::from(1);
+    };
+    // Replacing a corrupt file would silently unpin every other tree
+    let mut pins = match pin::load(&path) {
+        Ok(pins) => pins,
+        Err(e) => {
+            eprintln!(
+                "Cannot read {}: {e} — fix or remove it first",
+                path.display()
+            );
+            return ExitCode::from(1);
+        }
+    };
+    let count = scanned.len();
+    pins.pin(&root, scanned);
+    if let Err(e) = pin::save(&path, &pins) {
+        eprintln!("Cannot write {}: {e}", path.display());
+        return ExitCode::from(1);
+    }
+    println!("Pinned {count} .tlp file(s) under {}", root.display());
+    ExitCode::SUCCESS
+}
+
+// ─── Daemon ───
+//
+// Optional. Every tool call starts a fresh tlp-guard; with a daemon running,
+// tlp-guard hands the payload over its socket in the state directory instead
+// of finding the vault and parsing .tlp again. If the daemon is gone or
+// doesn't answer, tlp-guard evaluates the call itself.
+
+#[cfg(unix)]
+fn cmd_daemon() -> ExitCode {
+    use context_tlp::daemon;
+
+    let Some(socket) = daemon::socket_path() else {
+        eprintln!("Cannot determine the state directory (set XDG_STATE_HOME or HOME)");
+        return ExitCode::from(1);
+    };
+    let listener = match daemon::bind(&socket) {
+        Ok(l) => l,
+        Err(e) => {
+            eprintln!("Cannot listen on {}: {e}", socket.display());
+            return ExitCode::from(1);
+        }
+    };
+    eprintln!("tlp daemon listening on {}", socket.display());
+    daemon::serve(&listener);
+    ExitCode::SUCCESS
+}
+
+#[cfg(not(unix))]
+fn cmd_daemon() -> ExitCode {
+    eprintln!("tlp daemon needs Unix domain sockets");
+    ExitCode::from(1)
+}
+
+// ─── Session start ───
+//
+// safe-read and safe-write run via Bash and never see the hook payload.
+// Claude Code sources CLAUDE_ENV_FILE before each Bash command, so exporting
+// the id there lets them tag their audit records with the session. The id is
+// also recorded in the state directory as the default scope for `tlp allow`.
+
+#[derive(Deserialize)]
+struct SessionStartInput {
+    session_id: Option<String>,
+}
+
+fn cmd_session_start() -> ExitCode {
+    let mut buf = String::new();
+    if io::stdin().read_to_string(&mut buf).is_err() {
+        return ExitCode::SUCCESS;
+    }
+    let Some(session_id) = serde_json::from_str::<SessionStartInput>(&buf)
+        .ok()
+        .and_then(|input| input.session_id)
+    else {
+        return ExitCode::SUCCESS;
+    };
+
+    // The id is written into a shell script — accept only safe characters
+    if !state::is_valid_session_id(&session_id) {
+        eprintln!("Ignoring malformed session id");
+        return ExitCode::SUCCESS;
+    }
+
+    if let Some(path) = approval::session_path() {
+        let recorded = path
+            .parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|()| fs::write(&path, &session_id));
+        if let Err(e) = recorded {
+            eprintln!("Cannot write {}: {e}", path.display());
+        }
+    }
+
+    let Some(env_file) = env::var_os("CLAUDE_ENV_FILE") else {
+        return ExitCode::SUCCESS;
+    };
+    let line = format!("export {}={session_id}\n", state::SESSION_ENV);
+    let written = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&env_file)
+        .and_then(|mut f| f.write_all(line.as_bytes()));
+    if let Err(e) = written {
+        eprintln!("Cannot write {}: {e}", PathBuf::from(env_file).display());
+    }
+    ExitCode::SUCCESS
+}
@@ -0,0 +1,144 @@
+use crate::approval::{self, from_hex, to_hex};
+use crate::state;
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Version tag leading every signed message. Distinct from approval tokens,
+/// so a signed grant can never pass as an override.
+const MESSAGE_VERSION: &str = "tlp-break-glass1";
+
+/// Minutes a one-shot override waits to be used before it lapses.
+pub const ONE_SHOT_MINUTES: u64 = 15;
+
+/// A user's override letting the agent past the guard for one file.
+///
+/// Overrides name a single absolute path, carry the reason the user typed,
+/// and are signed with the approval key like grants. A one-shot override is
+/// consumed by the first tool call it lets through; otherwise it holds until
+/// `expires`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Override {
+    pub path: String,
+    pub reason: String,
+    pub granted: u64,
+    /// Unix seconds after which the override no longer applies.
+    pub expires: u64,
+    pub one_shot: bool,
+    /// Hex HMAC-SHA256 over all other fields.
+    #[serde(default)]
+    pub mac: String,
+}
+
+impl Override {
+    fn message(&self) -> String {
+        format!(
+            "{MESSAGE_VERSION}\n{}\n{}\n{}\n{}\n{}",
+            self.path, self.granted, self.expires, self.one_shot, self.reason
+        )
+    }
+
+    fn hmac(&self, key: &[u8]) -> HmacSha256 {
+        let mut mac = HmacSha256::new_from_slice(key).expect("HMAC accepts any key length");
+        mac.update(self.message().as_bytes());
+        mac
+    }
+
+    /// Sign the override with the approval key.
+    pub fn sign(&mut self, key: &[u8]) {
+        self.mac = to_hex(&self.hmac(key).finalize().into_bytes());
+    }
+
+    /// True if the override carries a valid signature for this key.
+    pub fn verify(&self, key: &[u8]) -> bool {
+        from_hex(&self.mac).is_some_and(|mac| self.hmac(key).verify_slice(&mac).is_ok())
+    }
+
+    /// True if the override is signed, unexpired and names this file.
+    pub fn applies(&self, key: &[u8], abs_path: &str, now: u64) -> bool {
+        now < self.expires && self.path == abs_path && self.verify(key)
+    }
+}
+
+/// Location of the overrides file in the state directory.
+pub fn overrides_path() -> Option<PathBuf> {
+    Some(state::state_dir()?.join("overrides.json"))
+}
+
+/// Load all overrides. A missing or unreadable file means none.
+pub fn load(path: &Path) -> Vec<Override> {
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+/// Replace the stored overrides, dropping any that have expired.
+pub fn save(path: &Path, overrides: &[Override], now: u64) -> io::Result<()> {
+    let live: Vec<&Override> = overrides.iter().filter(|o| now < o.expires).collect();
+    let json = serde_json::to_string_pretty(&live).map_err(io::Error::other)?;
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let tmp = path.with_extension("json.tmp");
+    fs::write(&tmp, json)?;
+    fs::rename(&tmp, path)
+}
+
+/// Find an override for the file in the given overrides file and use it:
+/// a one-shot override is removed before it is returned. If it cannot be
+/// removed, it is not honoured either.
+///
+/// To take a one-shot override, the file is first renamed to a name of this
+/// call's own, so two concurrent calls can't both use it: the loser finds no
+/// file and is refused.
+pub fn take_from(path: &Path, key: &[u8], abs_path: &str, now: u64) -> Option<Override> {
+    static CALLS: AtomicU64 = AtomicU64::new(0);
+    let found = load(path)
+        .into_iter()
+        .find(|o| o.applies(key, abs_path, now))?;
+    if !found.one_shot {
+        return Some(found);
+    }
+
+    let claimed = path.with_extension(format!(
+        "json.{}.{}",
+        std::process::id(),
+        CALLS.fetch_add(1, Ordering::Relaxed)
+    ));
+    fs::rename(path, &claimed).ok()?;
+    let mut overrides = load(&claimed);
+    let taken = overrides
+        .iter()
+        .position(|o| o.applies(key, abs_path, now))
+        .map(|i| overrides.remove(i));
+    // Put the rest back, with any override granted since the rename
+    overrides.extend(load(path));
+    let saved = save(path, &overrides, now);
+    let _ = fs::remove_file(&claimed);
+    saved.ok()?;
+    taken
+}
+
+/// Use the override for a file, if the user granted one. Without a signing
+/// key nothing is overridden (fail closed).
+pub fn take(file_path: &str) -> Option<Override> {
+    let key = approval::key_path().and_then(|p| approval::load_key(&p))?;
+    let abs = std::path::absolute(file_path).ok()?;
+    take_from(
+        &overrides_path()?,
+        &key,
+        &abs.to_string_lossy(),
+        state::now(),
+    )
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,116 @@
+use super::*;
+use tempfile::tempdir;
+
+const KEY: &[u8] = b"test-key";
+
+fn signed(path: &str, expires: u64, one_shot: bool) -> Override {
+    let mut o = Override {
+        path: path.to_string(),
+        reason: "fix broken YAML".to_string(),
+        granted: 0,
+        expires,
+        one_shot,
+        mac: String::new(),
+    };
+    o.sign(KEY);
+    o
+}
+
+#[test]
+fn test_applies_to_its_path_until_expiry() {
+    let o = signed("/vault/Contacts/anna.md", 200, false);
+    assert!(o.applies(KEY, "/vault/Contacts/anna.md", 100));
+    assert!(!o.applies(KEY, "/vault/Contacts/anna.md", 200));
+    assert!(!o.applies(KEY, "/vault/Contacts/bob.md", 100));
+    assert!(!o.applies(b"other-key", "/vault/Contacts/anna.md", 100));
+}
+
+#[test]
+fn test_tampering_invalidates_signature() {
+    let mut o = signed("/vault/Contacts/anna.md", 200, true);
+    o.one_shot = false;
+    assert!(!o.verify(KEY));
+
+    let mut o = signed("/vault/Contacts/anna.md", 200, true);
+    o.reason = "something else".to_string();
+    assert!(!o.verify(KEY));
+}
+
+#[test]
+fn test_one_shot_is_consumed() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("overrides.json");
+    let file = "/vault/Contacts/anna.md";
+    save(&path, &[signed(file, 200, true)], 100).unwrap();
+
+    assert!(take_from(&path, KEY, file, 100).is_some());
+    assert!(take_from(&path, KEY, file, 100).is_none());
+    assert!(load(&path).is_empty());
+}
+
+#[test]
+fn test_one_shot_take_leaves_the_others() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("overrides.json");
+    let file = "/vault/Contacts/anna.md";
+    save(
+        &path,
+        &[signed(file, 200, true), signed("/vault/b.md", 200, true)],
+        100,
+    )
+    .unwrap();
+
+    assert!(take_from(&path, KEY, file, 100).is_some());
+    let left = load(&path);
+    assert_eq!(left.len(), 1);
+    assert_eq!(left[0].path, "/vault/b.md");
+    // The claimed copy is gone
+    assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+}
+
+#[test]
+fn test_one_shot_is_taken_once_under_concurrency() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("overrides.json");
+    let file = "/vault/Contacts/anna.md";
+    save(&path, &[signed(file, 200, true)], 100).unwrap();
+
+    let taken = std::thread::scope(|s| {
+        let calls: Vec<_> = (0..8)
+            .map(|_| s.spawn(|| take_from(&path, KEY, file, 100).is_some()))
+            .collect();
+        calls
+            .into_iter()
+            .filter_map(|c| c.join().ok())
+            .filter(|&taken| taken)
+            .count()
+    });
+    assert_eq!(taken, 1);
+}
+
+#[test]
+fn test_time_boxed_is_reusable() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("overrides.json");
+    let file = "/vault/Contacts/anna.md";
+    save(&path, &[signed(file, 200, false)], 100).unwrap();
+
+    assert!(take_from(&path, KEY, file, 100).is_some());
+    assert!(take_from(&path, KEY, file, 150).is_some());
+    assert!(take_from(&path, KEY, file, 250).is_none());
+}
+
+#[test]
+fn test_save_drops_expired() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("overrides.json");
+    save(
+        &path,
+        &[signed("/a.md", 50, false), signed("/b.md", 200, true)],
+        100,
+    )
+    .unwrap();
+    let left = load(&path);
+    assert_eq!(left.len(), 1);
+    assert_eq!(left[0].path, "/b.md");
+}
@@ -0,0 +1,176 @@
+use crate::config::{self, Settings};
+use crate::pin::{self, Pins};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::time::SystemTime;
+
+/// Entries kept per map before the cache starts over.
+const MAX_ENTRIES: usize = 4096;
+
+/// Modification time and size of a file or directory; None if it is missing.
+type Stamp = Option<(SystemTime, u64)>;
+
+fn stamp(path: &Path) -> Stamp {
+    let meta = fs::metadata(path).ok()?;
+    Some((meta.modified().ok()?, meta.len()))
+}
+
+struct RootEntry {
+    root: Option<PathBuf>,
+    /// Every directory the lookup walked through, with its stamp.
+    walked: Vec<(PathBuf, Stamp)>,
+}
+
+struct ConfigEntry {
+    stamp: Stamp,
+    text: String,
+    settings: Result<Settings, String>,
+}
+
+struct PinsEntry {
+    stamp: Stamp,
+    pins: Result<Pins, String>,
+}
+
+/// Vault roots and parsed `.tlp` configs, kept by the guard daemon between
+/// tool calls.
+///
+/// Each entry remembers the stamps of what it was derived from and is
+/// recomputed as soon as one changes. A vault root depends on every directory
+/// walked through to find it (adding or removing a `.tlp` changes its
+/// directory's mtime); a config or a digest depends on its file, and the pins
+/// on the pins file.
+#[derive(Default)]
+pub struct Cache {
+    roots: HashMap<PathBuf, RootEntry>,
+    configs: HashMap<PathBuf, ConfigEntry>,
+    pins: HashMap<PathBuf, PinsEntry>,
+    digests: HashMap<PathBuf, (Stamp, String)>,
+}
+
+impl Cache {
+    /// Nearest directory at or above `start` holding a `.tlp`.
+    pub fn vault_root(&mut self, start: &Path) -> Option<PathBuf> {
+        if let Some(entry) = self.roots.get(start) {
+            if entry.walked.iter().all(|(dir, s)| stamp(dir) == *s) {
+                return entry.root.clone();
+            }
+        }
+
+        let mut walked = Vec::new();
+        let mut root = None;
+        let mut dir = Some(start);
+        while let Some(d) = dir {
+            walked.push((d.to_path_buf(), stamp(d)));
+            if d.join(".tlp").exists() {
+                root = Some(d.to_path_buf());
+                break;
+            }
+            dir = d.parent();
+        }
+
+        if self.roots.len() >= MAX_ENTRIES {
+            self.roots.clear();
+        }
+        self.roots.insert(
+            start.to_path_buf(),
+            RootEntry {
+                root: root.clone(),
+                walked,
+            },
+        );
+        root
+    }
+
+    fn config(&mut self, vault_root: &Path) -> Option<&ConfigEntry> {
+        let path = vault_root.join(".tlp");
+        // Stamp before reading: a change in between is caught on the next call
+        let current = Some(stamp(&path)?);
+        let fresh = self.configs.get(&path).is_some_and(|e| e.stamp == current);
+        if !fresh {
+            let text = fs::read_to_string(&path).ok()?;
+            let settings = config::parse(&text);
+            if self.configs.len() >= MAX_ENTRIES {
+                self.configs.clear();
+            }
+            self.configs.insert(
+                path.clone(),
+                ConfigEntry {
+                    stamp: current,
+                    text,
+                    settings,
+                },
+            );
+        }
+        self.configs.get(&path)
+    }
+
+    /// Text of the `.tlp` at a vault root. None if it can't be read.
+    pub fn text(&mut self, vault_root: &Path) -> Option<String> {
+        self.config(vault_root).map(|e| e.text.clone())
+    }
+
+    /// Settings of the `.tlp` at a vault root, the defaults if it can't be
+    /// read, or why it can't be parsed.
+    pub fn settings(&mut self, vault_root: &Path) -> Result<Settings, String> {
+        self.config(vault_root)
+            .map_or_else(|| Ok(Settings::default()), |e| e.settings.clone())
+    }
+
+    /// Pins recorded in a pins file, or why it can't be loaded.
+    pub fn pins(&mut self, path: &Path) -> Result<Pins, String> {
+        let current = stamp(path);
+        if let Some(entry) = self.pins.get(path).filter(|e| e.stamp == current) {
+            return entry.pins.clone();
+        }
+        let pins = pin::load(path).map_err(|e| e.to_string());
+        if self.pins.len() >= MAX_ENTRIES {
+            self.pins.clear();
+        }
+        self.pins.insert(
+            path.to_path_buf(),
+            PinsEntry {
+                stamp: current,
+                pins: pins.clone(),
+            },
+        );
+        pins
+    }
+
+    /// SHA-256 of a file, as `tlp pin` records it. None if it can't be read.
+    pub fn digest(&mut self, path: &Path) -> Option<String> {
+        let current = Some(stamp(path)?);
+        if let Some((s, digest)) = self.digests.get(path) {
+            if *s == current {
+                return Some(digest.clone());
+            }
+        }
+        let digest = pin::hash(&fs::read(path).ok()?);
+        if self.digests.len() >= MAX_ENTRIES {
+            self.digests.clear();
+        }
+        self.digests
+            .insert(path.to_path_buf(), (current, digest.clone()));
+        Some(digest)
+    }
+}
+
+static SHARED: OnceLock<Mutex<Cache>> = OnceLock::new();
+
+/// Turn on the process-wide cache. Only the long-lived daemon does this;
+/// one-shot processes read everything fresh.
+pub fn enable() {
+    let _ = SHARED.set(Mutex::new(Cache::default()));
+}
+
+/// Run a lookup against the process-wide cache. None if it is not enabled
+/// (or a panic poisoned it), in which case the caller reads from disk.
+pub fn with<R>(f: impl FnOnce(&mut Cache) -> R) -> Option<R> {
+    let mut cache = SHARED.get()?.lock().ok()?;
+    Some(f(&mut cache))
+}
+
+#[cfg(test)]
+mod tests;
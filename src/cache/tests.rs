@@ -0,0 +1,92 @@
+use super::*;
+use std::fs::File;
+use std::time::Duration;
+use tempfile::tempdir;
+
+/// Move a file's mtime forward, as a later edit would.
+fn touch(path: &Path) {
+    let later = SystemTime::now() + Duration::from_secs(5);
+    File::options()
+        .write(true)
+        .open(path)
+        .unwrap()
+        .set_modified(later)
+        .unwrap();
+}
+
+#[test]
+fn test_vault_root_follows_new_tlp() {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join(".tlp"), "").unwrap();
+    let sub = dir.path().join("Projects/work");
+    fs::create_dir_all(&sub).unwrap();
+
+    let mut cache = Cache::default();
+    assert_eq!(cache.vault_root(&sub), Some(dir.path().to_path_buf()));
+
+    // A nested vault appears below the cached root
+    fs::write(dir.path().join("Projects/.tlp"), "").unwrap();
+    assert_eq!(
+        cache.vault_root(&sub),
+        Some(dir.path().join("Projects").clone())
+    );
+
+    fs::remove_file(dir.path().join("Projects/.tlp")).unwrap();
+    assert_eq!(cache.vault_root(&sub), Some(dir.path().to_path_buf()));
+}
+
+#[test]
+fn test_config_reloads_when_changed() {
+    let dir = tempdir().unwrap();
+    let tlp = dir.path().join(".tlp");
+    fs::write(&tlp, "mode: enforce\n").unwrap();
+
+    let mut cache = Cache::default();
+    assert_eq!(
+        cache.settings(dir.path()).unwrap().mode,
+        config::Mode::Enforce
+    );
+
+    fs::write(&tlp, "mode: monitor\n").unwrap();
+    touch(&tlp);
+    assert_eq!(
+        cache.settings(dir.path()).unwrap().mode,
+        config::Mode::Monitor
+    );
+    assert_eq!(cache.text(dir.path()).as_deref(), Some("mode: monitor\n"));
+}
+
+#[test]
+fn test_missing_config() {
+    let dir = tempdir().unwrap();
+    let mut cache = Cache::default();
+    assert!(cache.text(dir.path()).is_none());
+    assert_eq!(
+        cache.settings(dir.path()).unwrap().mode,
+        config::Mode::Enforce
+    );
+}
+
+#[test]
+fn test_pins_and_digests_reload_when_changed() {
+    let dir = tempdir().unwrap();
+    let tlp = dir.path().join(".tlp");
+    fs::write(&tlp, "RED:\n").unwrap();
+    let pins_path = dir.path().join("pins.json");
+
+    let mut cache = Cache::default();
+    assert_eq!(cache.pins(&pins_path), Ok(Pins::default()));
+    assert_eq!(cache.digest(&tlp), Some(pin::hash(b"RED:\n")));
+
+    let mut pins = Pins::default();
+    pins.pin(dir.path(), pin::scan(dir.path()).unwrap());
+    pin::save(&pins_path, &pins).unwrap();
+    assert_eq!(cache.pins(&pins_path), Ok(pins));
+
+    fs::write(&tlp, "GREEN:\n").unwrap();
+    touch(&tlp);
+    assert_eq!(cache.digest(&tlp), Some(pin::hash(b"GREEN:\n")));
+
+    fs::remove_file(&tlp).unwrap();
+    assert_eq!(cache.digest(&tlp), None);
+}
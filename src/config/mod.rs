@@ -0,0 +1,376 @@
+use crate::cache;
+use crate::policy::Policy;
+use crate::tlp;
+use crate::vault;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Optional settings declared in `.tlp` alongside the level lists.
+///
+/// The level headers (`RED:`, `AMBER:`, ...) are parsed by `tlp::classify`;
+/// everything else is read here. Settings are optional: a config with no
+/// settings yields the defaults. One that isn't valid YAML, or holds an
+/// invalid setting, is malformed and classifies the whole vault RED.
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub mode: Mode,
+    pub tool_output: ToolOutput,
+    pub audit: Audit,
+    pub secrets: Secrets,
+    pub leak_detection: LeakDetection,
+    pub policy: Policy,
+    pub redaction: Redaction,
+}
+
+/// Environment variable that overrides the `mode` setting of every vault.
+pub const MODE_ENV: &str = "TLP_MODE";
+
+/// Whether the guards enforce their decisions or only record them.
+///
+/// ```yaml
+/// mode: monitor   # log would-be denials of AMBER and policy decisions, allow them
+/// ```
+#[derive(Debug, Default, Deserialize, PartialEq, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum Mode {
+    #[default]
+    Enforce,
+    /// Evaluate and log as usual, but let denied actions through.
+    Monitor,
+}
+
+impl Mode {
+    pub fn parse(s: &str) -> Option<Mode> {
+        match s {
+            "enforce" => Some(Mode::Enforce),
+            "monitor" => Some(Mode::Monitor),
+            _ => None,
+        }
+    }
+}
+
+impl Settings {
+    /// The `.tlp` mode. The CLI tools go by this alone: the agent controls
+    /// the environment of everything it runs through Bash.
+    pub fn monitoring(&self) -> bool {
+        self.mode == Mode::Monitor
+    }
+
+    /// Mode for the `tlp-guard` hook: `$TLP_MODE` if set to a known mode, else
+    /// the `.tlp` setting. Only the hook process may read the variable.
+    pub fn hook_mode(&self) -> Mode {
+        env::var(MODE_ENV)
+            .ok()
+            .and_then(|v| Mode::parse(v.trim()))
+            .unwrap_or(self.mode)
+    }
+
+    pub fn hook_monitoring(&self) -> bool {
+        self.hook_mode() == Mode::Monitor
+    }
+}
+
+/// What `tlp-scan` does when a tool response contains hidden content.
+#[derive(Debug, Default, Deserialize, PartialEq, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum ScanAction {
+    /// Withhold the result entirely.
+    Block,
+    /// Hand the model a redacted version of the result.
+    #[default]
+    Redact,
+    /// Let the result through, but warn the model and the user.
+    Warn,
+}
+
+/// Per-tool scan actions. The `default` key applies to unlisted tools.
+///
+/// ```yaml
+/// tool_output:
+///   default: redact
+///   Bash: block
+///   WebFetch: warn
+/// ```
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(transparent)]
+pub struct ToolOutput(HashMap<String, ScanAction>);
+
+impl ToolOutput {
+    /// Resolve the action for a tool, falling back to `default`, then `redact`.
+    pub fn action_for(&self, tool_name: &str) -> ScanAction {
+        self.0
+            .get(tool_name)
+            .or_else(|| self.0.get("default"))
+            .copied()
+            .unwrap_or_default()
+    }
+}
+
+/// Audit log of guard decisions.
+///
+/// ```yaml
+/// audit:
+///   path: ~/logs/tlp-audit.jsonl   # default: audit.jsonl in the state directory
+///   max_bytes: 10485760            # rotate when the log would exceed this
+///   keep: 5                        # rotated files to keep
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct Audit {
+    pub enabled: bool,
+    pub path: Option<PathBuf>,
+    pub max_bytes: u64,
+    pub keep: usize,
+}
+
+impl Default for Audit {
+    fn default() -> Self {
+        Audit {
+            enabled: true,
+            path: None,
+            max_bytes: 10 * 1024 * 1024,
+            keep: 5,
+        }
+    }
+}
+
+/// Paths the agent may write secrets into. `tlp-guard` denies Write, Edit and
+/// `MultiEdit` calls that add secret-pattern matches to any other file.
+///
+/// ```yaml
+/// secrets:
+///   allow:
+///     - "tests/fixtures/**"   # matched against every trailing part of the path
+///     - "*.example"
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct Secrets {
+    pub allow: Vec<String>,
+}
+
+impl Default for Secrets {
+    fn default() -> Self {
+        Secrets {
+            allow: vec!["fixtures/**".to_string(), "testdata/**".to_string()],
+        }
+    }
+}
+
+impl Secrets {
+    /// True if an allowlist pattern matches the path, or any part of it that
+    /// starts at a directory boundary — `fixtures/**` allows `/repo/tests/fixtures/key`.
+    pub fn allows(&self, path: &str) -> bool {
+        let tails =
+            std::iter::once(path).chain(path.match_indices('/').map(|(i, _)| &path[i + 1..]));
+        tails
+            .flat_map(|tail| self.allow.iter().map(move |p| (tail, p)))
+            .any(|(tail, pattern)| tlp::matches_pattern(tail, pattern))
+    }
+}
+
+/// What happens when text written below AMBER reproduces AMBER material.
+#[derive(Debug, Default, Deserialize, PartialEq, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum LeakAction {
+    /// Refuse the write.
+    #[default]
+    Deny,
+    /// Let the user decide (`tlp-guard`); `safe-write` cannot prompt and refuses.
+    Ask,
+}
+
+/// Cross-level leak detection. Text the agent writes into a GREEN or CLEAR
+/// file, or one outside any vault, is compared with the AMBER content
+/// disclosed to it earlier in the session.
+///
+/// ```yaml
+/// leak_detection:
+///   action: deny   # or ask
+///   ratio: 0.3     # share of the written shingles that came from AMBER content
+///   matches: 16    # or this many matching shingles, whatever the ratio
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct LeakDetection {
+    pub enabled: bool,
+    pub action: LeakAction,
+    pub ratio: f64,
+    pub matches: usize,
+}
+
+impl Default for LeakDetection {
+    fn default() -> Self {
+        LeakDetection {
+            enabled: true,
+            action: LeakAction::Deny,
+            ratio: 0.3,
+            matches: 16,
+        }
+    }
+}
+
+/// Whether `#tlp/*` markers inside code count.
+#[derive(Debug, Default, Deserialize, PartialEq, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum CodeMarkers {
+    /// Markers in code fences and inline code are examples, shown as written.
+    #[default]
+    Ignore,
+    /// Markers redact wherever they appear.
+    Redact,
+}
+
+/// Other ways of marking private text, hidden exactly like `#tlp/red` blocks.
+#[derive(Debug, Deserialize, PartialEq, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum Syntax {
+    /// Obsidian comments: `%%inline%%`, or `%%` lines around a block.
+    Comments,
+    /// `> [!private]` callouts, with every quoted line that follows.
+    Callouts,
+    /// `<details class="private">` … `</details>`.
+    Details,
+}
+
+/// Personal data detectors, off unless listed.
+#[derive(Debug, Deserialize, PartialEq, Clone, Copy)]
+#[serde(rename_all = "kebab-case")]
+pub enum Pii {
+    /// Email addresses.
+    Email,
+    /// E.164 (`+44 20 7946 0958`) and common national phone formats.
+    Phone,
+    /// IBANs that pass the mod-97 checksum.
+    Iban,
+    /// Payment card numbers that pass the Luhn check.
+    Card,
+    /// US SSNs, UK National Insurance and Canadian Social Insurance numbers.
+    NationalId,
+}
+
+/// How `safe-read`, `safe-write` and `tlp-scan` find the sections they hide.
+///
+/// ```yaml
+/// redaction:
+///   code: ignore   # or redact: honour markers inside code fences and `code`
+///   syntaxes: [comments, callouts, details]   # none by default
+///   reveal: [health]   # #tlp/red/<compartment> sections shown anyway
+///   fields: [phone, salary]   # frontmatter and `key:: value` fields to hide
+///   pii: [email, phone, iban, card, national-id]   # none by default
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct Redaction {
+    pub code: CodeMarkers,
+    pub syntaxes: Vec<Syntax>,
+    /// Compartments of `#tlp/red/<compartment>` left visible. Grants from
+    /// `tlp approve`/`tlp allow --reveal` add more for the files they cover.
+    pub reveal: Vec<String>,
+    /// Frontmatter and Dataview inline fields whose values are hidden; the
+    /// keys stay visible.
+    pub fields: Vec<String>,
+    pub pii: Vec<Pii>,
+    /// Who the view is for: sections marked above this level are hidden.
+    /// Set by the caller, not by `.tlp`; AMBER hides only `#tlp/red`.
+    #[serde(skip, default = "default_audience")]
+    pub audience: tlp::Tlp,
+}
+
+fn default_audience() -> tlp::Tlp {
+    tlp::Tlp::Amber
+}
+
+impl Default for Redaction {
+    fn default() -> Self {
+        Redaction {
+            code: CodeMarkers::default(),
+            syntaxes: Vec::new(),
+            reveal: Vec::new(),
+            fields: Vec::new(),
+            pii: Vec::new(),
+            audience: default_audience(),
+        }
+    }
+}
+
+impl Redaction {
+    pub fn ignores_code(&self) -> bool {
+        self.code == CodeMarkers::Ignore
+    }
+
+    pub fn enables(&self, syntax: Syntax) -> bool {
+        self.syntaxes.contains(&syntax)
+    }
+
+    pub fn detects(&self, pii: Pii) -> bool {
+        self.pii.contains(&pii)
+    }
+
+    /// True if the field's value is hidden. Keys match case-insensitively,
+    /// as in Dataview.
+    pub fn hides_field(&self, key: &str) -> bool {
+        self.fields
+            .iter()
+            .any(|f| f.eq_ignore_ascii_case(key.trim()))
+    }
+
+    /// True if the compartment, or one it is nested in (`health` for
+    /// `health/dental`), is revealed.
+    pub fn reveals(&self, compartment: &str) -> bool {
+        self.reveal.iter().any(|r| {
+            compartment
+                .strip_prefix(r.as_str())
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+        })
+    }
+}
+
+/// Parse settings from `.tlp` content. A config that isn't valid YAML, or
+/// whose settings have the wrong shape, is an error: dropping it silently
+/// would drop its policy, redaction and secret settings with it.
+pub fn parse(config: &str) -> Result<Settings, String> {
+    // An empty or comment-only config is a null document: all defaults
+    match serde_yaml::from_str::<Option<Settings>>(config) {
+        Ok(settings) => Ok(settings.unwrap_or_default()),
+        Err(e) => Err(format!("Malformed .tlp config: {e}")),
+    }
+}
+
+/// Read the `.tlp` at a vault root, from the daemon's cache when it is enabled.
+pub fn read(vault_root: &Path) -> Option<String> {
+    if let Some(text) = cache::with(|c| c.text(vault_root)) {
+        return text;
+    }
+    fs::read_to_string(vault_root.join(".tlp")).ok()
+}
+
+/// Load settings from the `.tlp` at a vault root, or why they can't be parsed.
+/// The defaults if there is no readable `.tlp`.
+pub fn try_load(vault_root: &Path) -> Result<Settings, String> {
+    if let Some(settings) = cache::with(|c| c.settings(vault_root)) {
+        return settings;
+    }
+    read(vault_root).map_or_else(|| Ok(Settings::default()), |config| parse(&config))
+}
+
+/// Load settings from the `.tlp` at a vault root. A malformed config yields
+/// the defaults here, but `tlp::classify_file` reports it and classifies
+/// every file in the vault RED, so nothing is read under them.
+pub fn load(vault_root: &Path) -> Settings {
+    try_load(vault_root).unwrap_or_default()
+}
+
+/// Settings of the vault a file is in, or the defaults outside any vault.
+pub fn for_file(file_path: &str) -> Settings {
+    vault::find_vault(file_path)
+        .map(|root| load(&root))
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,143 @@
+use super::*;
+
+#[test]
+fn test_empty_config_uses_defaults() {
+    let settings = parse("").unwrap();
+    assert_eq!(settings.tool_output.action_for("Bash"), ScanAction::Redact);
+}
+
+#[test]
+fn test_level_lists_are_ignored() {
+    let settings = parse("RED:\n  - \"*.pdf\"\n\nGREEN:\n  - \"Topics/**\"\n").unwrap();
+    assert_eq!(settings.tool_output.action_for("Read"), ScanAction::Redact);
+}
+
+#[test]
+fn test_invalid_config_is_an_error() {
+    assert!(parse("**/*.md RED\n").is_err());
+    let error = parse("redaction:\n  pii: [emial]\n").unwrap_err();
+    assert!(error.starts_with("Malformed .tlp config"));
+    assert!(parse("# only comments\n").is_ok());
+}
+
+#[test]
+fn test_tool_output_per_tool() {
+    let config = "\
+RED:
+  - \"*.pdf\"
+
+tool_output:
+  Bash: block
+  WebFetch: warn
+";
+    let settings = parse(config).unwrap();
+    assert_eq!(settings.tool_output.action_for("Bash"), ScanAction::Block);
+    assert_eq!(
+        settings.tool_output.action_for("WebFetch"),
+        ScanAction::Warn
+    );
+    assert_eq!(settings.tool_output.action_for("Grep"), ScanAction::Redact);
+}
+
+#[test]
+fn test_tool_output_default_key() {
+    let settings = parse("tool_output:\n  default: warn\n  Bash: block\n").unwrap();
+    assert_eq!(settings.tool_output.action_for("Bash"), ScanAction::Block);
+    assert_eq!(
+        settings.tool_output.action_for("mcp__db__query"),
+        ScanAction::Warn
+    );
+}
+
+#[test]
+fn test_audit_defaults() {
+    let settings = parse("").unwrap();
+    assert!(settings.audit.enabled);
+    assert!(settings.audit.path.is_none());
+    assert_eq!(settings.audit.keep, 5);
+}
+
+#[test]
+fn test_audit_partial_override() {
+    let settings = parse("audit:\n  path: /var/log/tlp.jsonl\n  keep: 2\n").unwrap();
+    assert_eq!(
+        settings.audit.path.as_deref(),
+        Some(Path::new("/var/log/tlp.jsonl"))
+    );
+    assert_eq!(settings.audit.keep, 2);
+    assert_eq!(settings.audit.max_bytes, 10 * 1024 * 1024);
+}
+
+#[test]
+fn test_secrets_default_allowlist() {
+    let settings = parse("").unwrap();
+    assert!(settings.secrets.allows("/repo/tests/fixtures/keys.md"));
+    assert!(settings.secrets.allows("/repo/pkg/testdata/token.txt"));
+    assert!(!settings.secrets.allows("/repo/README.md"));
+    assert!(!settings.secrets.allows("/repo/myfixtures/key.md"));
+}
+
+#[test]
+fn test_secrets_custom_allowlist() {
+    let settings =
+        parse("secrets:\n  allow:\n    - \"docs/samples/**\"\n    - \"*.example\"\n").unwrap();
+    assert!(settings.secrets.allows("/vault/docs/samples/aws.md"));
+    assert!(settings.secrets.allows("/vault/.env.example"));
+    assert!(!settings.secrets.allows("/vault/tests/fixtures/keys.md"));
+}
+
+#[test]
+fn test_mode_defaults_to_enforce() {
+    assert_eq!(parse("").unwrap().mode, Mode::Enforce);
+    assert_eq!(parse("mode: monitor\n").unwrap().mode, Mode::Monitor);
+    assert_eq!(Mode::parse("enforce"), Some(Mode::Enforce));
+    assert_eq!(Mode::parse("dry-run"), None);
+}
+
+#[test]
+fn test_redaction_ignores_code_by_default() {
+    assert!(parse("").unwrap().redaction.ignores_code());
+    let settings = parse("redaction:\n  code: redact\n").unwrap();
+    assert_eq!(settings.redaction.code, CodeMarkers::Redact);
+    assert!(!settings.redaction.ignores_code());
+}
+
+#[test]
+fn test_redaction_syntaxes() {
+    assert!(parse("").unwrap().redaction.syntaxes.is_empty());
+    let settings = parse("redaction:\n  syntaxes: [comments, details]\n").unwrap();
+    assert!(settings.redaction.enables(Syntax::Comments));
+    assert!(!settings.redaction.enables(Syntax::Callouts));
+    assert!(settings.redaction.enables(Syntax::Details));
+}
+
+#[test]
+fn test_redaction_reveal() {
+    let redaction = parse("redaction:\n  reveal: [health]\n").unwrap().redaction;
+    assert!(redaction.reveals("health"));
+    assert!(redaction.reveals("health/dental"));
+    assert!(!redaction.reveals("healthcare"));
+    assert!(!redaction.reveals("finance"));
+}
+
+#[test]
+fn test_redaction_fields() {
+    let redaction = parse("redaction:\n  fields: [phone, Salary]\n")
+        .unwrap()
+        .redaction;
+    assert!(redaction.hides_field("phone"));
+    assert!(redaction.hides_field("salary"));
+    assert!(redaction.hides_field("PHONE"));
+    assert!(!redaction.hides_field("name"));
+}
+
+#[test]
+fn test_redaction_pii() {
+    assert!(parse("").unwrap().redaction.pii.is_empty());
+    let redaction = parse("redaction:\n  pii: [email, national-id]\n")
+        .unwrap()
+        .redaction;
+    assert!(redaction.detects(Pii::Email));
+    assert!(redaction.detects(Pii::NationalId));
+    assert!(!redaction.detects(Pii::Card));
+}
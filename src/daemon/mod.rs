@@ -0,0 +1,203 @@
+use crate::cache;
+use crate::config;
+use crate::guard::{self, Response};
+use crate::state;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::net::Shutdown;
+use std::os::unix::fs::PermissionsExt;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// How long `tlp-guard` waits for the daemon before evaluating in-process.
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// How long the daemon waits on a client that stops sending or reading.
+const SERVER_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Largest request the daemon reads. Payloads carry whole Write contents.
+const MAX_REQUEST: u64 = 64 * 1024 * 1024;
+
+/// Hash of the sources this binary was built from (see build.rs). Client and
+/// daemon must match exactly: the crate version doesn't change between
+/// local builds.
+const BUILD_ID: &str = env!("TLP_BUILD_ID");
+
+/// Environment variables that change the guard's decisions. The daemon only
+/// answers clients whose values match its own.
+const ENV_KEYS: [&str; 4] = [
+    state::STATE_DIR_ENV,
+    "XDG_STATE_HOME",
+    "HOME",
+    config::MODE_ENV,
+];
+
+#[derive(Serialize, Deserialize)]
+struct Request {
+    build: String,
+    env: BTreeMap<String, String>,
+    payload: String,
+}
+
+#[derive(Serialize, Deserialize)]
+enum Reply {
+    Evaluated(Response),
+    /// The daemon won't answer for this client; it evaluates in-process.
+    Refused(String),
+}
+
+fn environment() -> BTreeMap<String, String> {
+    ENV_KEYS
+        .iter()
+        .filter_map(|k| Some((k.to_string(), env::var(k).ok()?)))
+        .collect()
+}
+
+/// Location of the daemon's socket in the state directory.
+pub fn socket_path() -> Option<PathBuf> {
+    Some(state::state_dir()?.join("guard.sock"))
+}
+
+/// Ask the daemon to evaluate a hook payload. None if no daemon is listening,
+/// the process listening isn't a `tlp` daemon of this user, it doesn't answer
+/// in time, or it refuses (another build or environment): the caller then
+/// evaluates in-process.
+pub fn query(payload: &str) -> Option<Response> {
+    query_at(&socket_path()?, payload)
+}
+
+/// `query` against a specific socket.
+pub fn query_at(socket: &Path, payload: &str) -> Option<Response> {
+    let mut stream = UnixStream::connect(socket).ok()?;
+    // Whoever owns the socket decides what the hook answers; don't take
+    // that from anyone but our own daemon
+    if !is_daemon(&stream) {
+        return None;
+    }
+    stream.set_read_timeout(Some(CLIENT_TIMEOUT)).ok()?;
+    stream.set_write_timeout(Some(CLIENT_TIMEOUT)).ok()?;
+
+    let request = Request {
+        build: BUILD_ID.to_string(),
+        env: environment(),
+        payload: payload.to_string(),
+    };
+    stream.write_all(&serde_json::to_vec(&request).ok()?).ok()?;
+    stream.shutdown(Shutdown::Write).ok()?;
+
+    let mut reply = Vec::new();
+    stream.read_to_end(&mut reply).ok()?;
+    match serde_json::from_slice(&reply).ok()? {
+        Reply::Evaluated(response) => Some(response),
+        Reply::Refused(_) => None,
+    }
+}
+
+/// True if the peer is a `tlp` binary installed next to this one, or this
+/// very executable, running as the same user. Checked through `SO_PEERCRED`
+/// and `/proc/<pid>/exe`; a daemon whose binary was replaced since it started
+/// no longer matches.
+#[cfg(target_os = "linux")]
+fn is_daemon(stream: &UnixStream) -> bool {
+    let Ok(peer) = rustix::net::sockopt::socket_peercred(stream) else {
+        return false;
+    };
+    if peer.uid != rustix::process::getuid() {
+        return false;
+    }
+    let (Ok(exe), Ok(own)) = (
+        fs::read_link(format!("/proc/{}/exe", peer.pid.as_raw_pid())),
+        env::current_exe(),
+    ) else {
+        return false;
+    };
+    exe == own || own.parent().is_some_and(|dir| exe == dir.join("tlp"))
+}
+
+/// Without `SO_PEERCRED` the peer can't be checked, so the daemon is never
+/// trusted and `tlp-guard` always evaluates in-process.
+#[cfg(not(target_os = "linux"))]
+fn is_daemon(_stream: &UnixStream) -> bool {
+    false
+}
+
+/// True if the peer runs as the same user as this process.
+#[cfg(target_os = "linux")]
+fn same_user(stream: &UnixStream) -> bool {
+    rustix::net::sockopt::socket_peercred(stream)
+        .is_ok_and(|peer| peer.uid == rustix::process::getuid())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn same_user(_stream: &UnixStream) -> bool {
+    true
+}
+
+/// Bind the daemon's socket, readable and writable by the owner only.
+/// A stale socket left by a daemon that died is replaced; a live one is not.
+pub fn bind(socket: &Path) -> io::Result<UnixListener> {
+    if UnixStream::connect(socket).is_ok() {
+        return Err(io::Error::new(
+            io::ErrorKind::AddrInUse,
+            "another daemon is listening",
+        ));
+    }
+    if fs::symlink_metadata(socket).is_ok() {
+        fs::remove_file(socket)?;
+    }
+    if let Some(parent) = socket.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let listener = UnixListener::bind(socket)?;
+    fs::set_permissions(socket, fs::Permissions::from_mode(0o600))?;
+    Ok(listener)
+}
+
+fn handle(mut stream: UnixStream) -> io::Result<()> {
+    stream.set_read_timeout(Some(SERVER_TIMEOUT))?;
+    stream.set_write_timeout(Some(SERVER_TIMEOUT))?;
+
+    let mut buf = Vec::new();
+    (&mut stream).take(MAX_REQUEST).read_to_end(&mut buf)?;
+    let reply = match serde_json::from_slice::<Request>(&buf) {
+        _ if !same_user(&stream) => Reply::Refused("client runs as another user".to_string()),
+        Ok(request) if request.build != BUILD_ID => {
+            Reply::Refused(format!("daemon runs build {BUILD_ID}"))
+        }
+        Ok(request) if request.env != environment() => {
+            Reply::Refused("environment differs from the daemon's".to_string())
+        }
+        // A panic must not take the daemon down; the client evaluates instead
+        Ok(request) => std::panic::catch_unwind(|| guard::evaluate(&request.payload)).map_or_else(
+            |_| Reply::Refused("evaluation failed".to_string()),
+            Reply::Evaluated,
+        ),
+        Err(e) => Reply::Refused(format!("malformed request: {e}")),
+    };
+    stream.write_all(&serde_json::to_vec(&reply).map_err(io::Error::other)?)
+}
+
+/// Answer guard requests until the process is stopped, keeping vault roots
+/// and parsed configs cached between them.
+pub fn serve(listener: &UnixListener) {
+    cache::enable();
+    for stream in listener.incoming() {
+        match stream {
+            Ok(stream) => {
+                std::thread::spawn(move || {
+                    if let Err(e) = handle(stream) {
+                        eprintln!("tlp daemon: {e}");
+                    }
+                });
+            }
+            Err(e) => eprintln!("tlp daemon: {e}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests;
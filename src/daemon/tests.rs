@@ -0,0 +1,75 @@
+use super::*;
+use tempfile::tempdir;
+
+/// Bind a socket and answer one connection without enabling the cache.
+fn serve_once(dir: &Path) -> PathBuf {
+    let socket = dir.join("guard.sock");
+    let listener = bind(&socket).unwrap();
+    std::thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        handle(stream).unwrap();
+    });
+    socket
+}
+
+fn red_vault(dir: &Path) -> String {
+    fs::write(dir.join(".tlp"), "RED:\n  - \"*.pdf\"\n").unwrap();
+    let file = dir.join("secret.pdf");
+    fs::write(&file, "binary").unwrap();
+    file.to_string_lossy().to_string()
+}
+
+#[test]
+fn test_query_matches_in_process_evaluation() {
+    let dir = tempdir().unwrap();
+    let file = red_vault(dir.path());
+    let payload = format!(r#"{{"tool_name":"Read","tool_input":{{"file_path":"{file}"}}}}"#);
+    let socket = serve_once(dir.path());
+
+    let response = query_at(&socket, &payload).unwrap();
+    assert_eq!(response.code, 2);
+    assert!(response.stderr.contains("TLP:RED"));
+}
+
+#[test]
+fn test_query_without_daemon_is_none() {
+    let dir = tempdir().unwrap();
+    assert!(query_at(&dir.path().join("guard.sock"), "{}").is_none());
+}
+
+#[test]
+fn test_other_build_is_refused() {
+    let dir = tempdir().unwrap();
+    let socket = serve_once(dir.path());
+
+    let mut stream = UnixStream::connect(&socket).unwrap();
+    let request = Request {
+        build: "0000000000000000".to_string(),
+        env: environment(),
+        payload: "{}".to_string(),
+    };
+    stream
+        .write_all(&serde_json::to_vec(&request).unwrap())
+        .unwrap();
+    stream.shutdown(Shutdown::Write).unwrap();
+    let mut reply = Vec::new();
+    stream.read_to_end(&mut reply).unwrap();
+    assert!(matches!(
+        serde_json::from_slice(&reply).unwrap(),
+        Reply::Refused(_)
+    ));
+}
+
+#[test]
+fn test_bind_replaces_stale_socket_but_not_live_one() {
+    let dir = tempdir().unwrap();
+    let socket = dir.path().join("guard.sock");
+    fs::write(&socket, "").unwrap();
+
+    let _listener = bind(&socket).unwrap();
+    let mode = fs::metadata(&socket).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o600);
+
+    let err = bind(&socket).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::AddrInUse);
+}
@@ -0,0 +1,177 @@
+use crate::config::{self, LeakAction};
+use crate::state;
+use crate::tlp;
+use std::collections::HashSet;
+use std::fmt::Write as _;
+use std::fs::{self, OpenOptions};
+use std::hash::BuildHasher;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// Words per shingle. Fixed, so fingerprints recorded under one vault's
+/// settings stay comparable with text checked under another's.
+pub const SHINGLE_WORDS: usize = 8;
+
+/// Multiplier of the polynomial rolling hash over word hashes.
+const BASE: u64 = 1_000_003;
+
+/// FNV-1a hash of a normalized word.
+fn word_hash(word: &str) -> u64 {
+    word.bytes().fold(0xcbf2_9ce4_8422_2325, |h, b| {
+        (h ^ u64::from(b)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// Hashes of every run of `SHINGLE_WORDS` consecutive words.
+///
+/// Words are lowercased alphanumeric runs, so reflowed lines, changed
+/// punctuation or markdown decoration don't break a match. Each window's hash
+/// is rolled from the previous one instead of rehashing all of its words.
+pub fn shingles(text: &str) -> HashSet<u64> {
+    let words: Vec<u64> = text
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(|w| word_hash(&w.to_lowercase()))
+        .collect();
+
+    let mut result = HashSet::new();
+    if words.len() < SHINGLE_WORDS {
+        return result;
+    }
+
+    // Weight of the word leaving the window: BASE^(SHINGLE_WORDS - 1)
+    let outgoing = (1..SHINGLE_WORDS).fold(1u64, |p, _| p.wrapping_mul(BASE));
+    let mut hash = words[..SHINGLE_WORDS]
+        .iter()
+        .fold(0u64, |h, &w| h.wrapping_mul(BASE).wrapping_add(w));
+    result.insert(hash);
+
+    for i in SHINGLE_WORDS..words.len() {
+        hash = hash
+            .wrapping_sub(words[i - SHINGLE_WORDS].wrapping_mul(outgoing))
+            .wrapping_mul(BASE)
+            .wrapping_add(words[i]);
+        result.insert(hash);
+    }
+    result
+}
+
+/// How much of a text reproduces known shingles.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Overlap {
+    pub matched: usize,
+    pub total: usize,
+}
+
+impl Overlap {
+    /// True if the overlap crosses either configured limit.
+    pub fn is_leak(&self, settings: &config::LeakDetection) -> bool {
+        if self.matched == 0 {
+            return false;
+        }
+        #[allow(clippy::cast_precision_loss)] // shingle counts are far below 2^52
+        let ratio = self.matched as f64 / self.total as f64;
+        ratio >= settings.ratio || self.matched >= settings.matches
+    }
+}
+
+/// Compare a text against known shingles.
+pub fn overlap<S: BuildHasher>(known: &HashSet<u64, S>, text: &str) -> Overlap {
+    let written = shingles(text);
+    Overlap {
+        matched: written.iter().filter(|h| known.contains(h)).count(),
+        total: written.len(),
+    }
+}
+
+/// Location of a session's fingerprint file in the state directory.
+/// None for malformed session ids, which must not become file names.
+pub fn session_path(session_id: &str) -> Option<PathBuf> {
+    if !state::is_valid_session_id(session_id) {
+        return None;
+    }
+    Some(
+        state::state_dir()?
+            .join("fingerprints")
+            .join(format!("{session_id}.txt")),
+    )
+}
+
+/// Load fingerprints: one hex hash per line. Missing file means none.
+pub fn load(path: &Path) -> HashSet<u64> {
+    fs::read_to_string(path)
+        .map(|content| {
+            content
+                .lines()
+                .filter_map(|line| u64::from_str_radix(line, 16).ok())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Append the shingles of a text to a fingerprint file.
+pub fn append(path: &Path, text: &str) -> io::Result<()> {
+    let hashes = shingles(text);
+    if hashes.is_empty() {
+        return Ok(());
+    }
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let lines = hashes.iter().fold(String::new(), |mut out, h| {
+        let _ = writeln!(out, "{h:016x}");
+        out
+    });
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?
+        .write_all(lines.as_bytes())
+}
+
+/// Remember AMBER content disclosed to the agent in this session.
+/// Best effort: without a session id there is nothing to correlate with.
+pub fn record_disclosure(session_id: Option<&str>, text: &str) {
+    let Some(path) = session_id.and_then(session_path) else {
+        return;
+    };
+    if let Err(e) = append(&path, text) {
+        eprintln!("TLP: cannot record fingerprints in {}: {e}", path.display());
+    }
+}
+
+/// A write that reproduces AMBER material, and what to do about it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Leak {
+    pub overlap: Overlap,
+    pub action: LeakAction,
+}
+
+/// Check text about to be written into a file against the AMBER content
+/// disclosed in this session. Only files below AMBER are checked: GREEN,
+/// CLEAR, or outside any vault.
+pub fn check_write(file_path: &str, session_id: Option<&str>, text: &str) -> Option<Leak> {
+    let settings = match tlp::classify_file(file_path) {
+        Some(c) if matches!(c.level, tlp::Tlp::Green | tlp::Tlp::Clear) => {
+            config::load(&c.vault_root).leak_detection
+        }
+        Some(_) => return None,
+        None => config::LeakDetection::default(),
+    };
+    if !settings.enabled {
+        return None;
+    }
+
+    let known = load(&session_id.and_then(session_path)?);
+    if known.is_empty() {
+        return None;
+    }
+    let overlap = overlap(&known, text);
+    overlap.is_leak(&settings).then_some(Leak {
+        overlap,
+        action: settings.action,
+    })
+}
+
+#[cfg(test)]
+mod tests;
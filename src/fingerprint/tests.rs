@@ -0,0 +1,78 @@
+use super::*;
+use tempfile::tempdir;
+
+const JOURNAL: &str = "Today I told Anna that the merger with Northwind falls through \
+                       unless the board signs before Friday. Nobody else knows yet.";
+
+#[test]
+fn test_short_text_has_no_shingles() {
+    assert!(shingles("only seven words in this short line").is_empty());
+    assert_eq!(
+        shingles("exactly eight words are in this short line").len(),
+        1
+    );
+}
+
+#[test]
+fn test_shingles_ignore_case_punctuation_and_layout() {
+    let reflowed = "today i told ANNA that the merger\n> with *Northwind* falls through, \
+                    unless the board signs before friday; nobody else knows yet";
+    assert_eq!(shingles(JOURNAL), shingles(reflowed));
+}
+
+#[test]
+fn test_rolling_hash_matches_direct_hash() {
+    // Each window hashed from scratch must equal the rolled value
+    let text = "a b c d e f g h i j k";
+    let direct: HashSet<u64> = [
+        "a b c d e f g h",
+        "b c d e f g h i",
+        "c d e f g h i j",
+        "d e f g h i j k",
+    ]
+    .iter()
+    .flat_map(|w| shingles(w))
+    .collect();
+    assert_eq!(shingles(text), direct);
+}
+
+#[test]
+fn test_overlap_of_pasted_paragraph() {
+    let known = shingles(JOURNAL);
+    let pasted = format!("Meeting summary.\n\n{JOURNAL}\n");
+    let o = overlap(&known, &pasted);
+    assert_eq!(o.matched, known.len());
+    assert!(o.is_leak(&config::LeakDetection::default()));
+
+    let unrelated = overlap(
+        &known,
+        "The Rust borrow checker enforces aliasing rules at compile time.",
+    );
+    assert_eq!(unrelated.matched, 0);
+    assert!(!unrelated.is_leak(&config::LeakDetection::default()));
+}
+
+#[test]
+fn test_is_leak_by_ratio_or_count() {
+    let settings = config::LeakDetection {
+        ratio: 0.5,
+        matches: 10,
+        ..Default::default()
+    };
+    let o = |matched, total| Overlap { matched, total };
+    assert!(o(5, 10).is_leak(&settings));
+    assert!(!o(4, 10).is_leak(&settings));
+    assert!(o(10, 1000).is_leak(&settings));
+    assert!(!o(0, 0).is_leak(&settings));
+}
+
+#[test]
+fn test_append_and_load_roundtrip() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("fingerprints/s1.txt");
+    append(&path, JOURNAL).unwrap();
+    append(&path, "short").unwrap();
+
+    assert_eq!(load(&path), shingles(JOURNAL));
+    assert!(load(&dir.path().join("missing.txt")).is_empty());
+}
@@ -0,0 +1,684 @@
+use crate::approval;
+use crate::audit::{self, Decision};
+use crate::breakglass;
+use crate::config;
+use crate::fingerprint;
+use crate::policy::{self, Action, Effect, Policy};
+use crate::redact;
+use crate::state;
+use crate::tamper;
+use crate::tlp::{self, Classification};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+/// JSON payload from Claude Code's `PreToolUse` hook.
+#[derive(Deserialize)]
+struct HookInput {
+    session_id: Option<String>,
+    tool_name: Option<String>,
+    tool_input: Option<ToolInput>,
+    cwd: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ToolInput {
+    file_path: Option<String>,
+    /// Grep, Glob
+    path: Option<String>,
+    /// Bash
+    command: Option<String>,
+    /// Write
+    content: Option<String>,
+    /// Edit
+    old_string: Option<String>,
+    new_string: Option<String>,
+    replace_all: Option<bool>,
+    /// `MultiEdit`
+    edits: Option<Vec<EditInput>>,
+}
+
+#[derive(Deserialize)]
+struct EditInput {
+    old_string: Option<String>,
+    new_string: Option<String>,
+    replace_all: Option<bool>,
+}
+
+impl ToolInput {
+    /// All text the tool call would put into the file.
+    fn written_text(&self) -> Vec<&str> {
+        let edits = self.edits.iter().flatten().map(|e| e.new_string.as_deref());
+        [self.content.as_deref(), self.new_string.as_deref()]
+            .into_iter()
+            .chain(edits)
+            .flatten()
+            .collect()
+    }
+
+    /// The file as the tool call would leave it: the Write content, or the
+    /// existing text with each edit applied. Edits whose `old_string` isn't
+    /// found are skipped; the tool itself rejects them.
+    fn resulting_content(&self, existing: &str) -> String {
+        if let Some(content) = &self.content {
+            return content.clone();
+        }
+        let single = [(
+            self.old_string.as_deref(),
+            self.new_string.as_deref(),
+            self.replace_all,
+        )];
+        let edits = self.edits.iter().flatten().map(|e| {
+            (
+                e.old_string.as_deref(),
+                e.new_string.as_deref(),
+                e.replace_all,
+            )
+        });
+        single
+            .into_iter()
+            .chain(edits)
+            .fold(existing.to_string(), |text, edit| match edit {
+                (Some(old), Some(new), all) if !old.is_empty() && text.contains(old) => {
+                    if all.unwrap_or(false) {
+                        text.replace(old, new)
+                    } else {
+                        text.replacen(old, new, 1)
+                    }
+                }
+                _ => text,
+            })
+    }
+}
+
+/// Outcome of a guard check. `Allow` may carry a note for stdout;
+/// `Deny` carries the reason shown to the model. `Redact` blocks the native
+/// tool but hands the model the `safe-read` view in its place. `Ask` leaves the
+/// decision to the user with the given reason. `Override` lets a call the policy
+/// blocks through on the user's break-glass override, with a notice for both.
+enum Verdict {
+    Allow(Option<String>),
+    Override {
+        notice: String,
+        reason: String,
+    },
+    Deny(String),
+    Ask(String),
+    Redact {
+        message: String,
+        redactions: usize,
+        secrets: usize,
+    },
+}
+
+/// True if the path points into the TLP state directory (approvals, audit log).
+fn in_state_dir(file_path: &str) -> bool {
+    let (Some(dir), Ok(abs)) = (state::state_dir(), std::path::absolute(file_path)) else {
+        return false;
+    };
+    std::path::absolute(dir).is_ok_and(|dir| abs.starts_with(dir))
+}
+
+/// True if the file has `#tlp/red` sections or secrets that native tools would expose.
+fn has_hidden_content(file_path: &str) -> bool {
+    fs::read_to_string(file_path).is_ok_and(|content| {
+        redact::has_hidden_content_with(&content, &config::for_file(file_path).redaction)
+    })
+}
+
+/// Number of secret matches the written text would add to the file.
+/// Secrets already present in the file are not counted.
+fn introduced_secrets(file_path: &str, written: &[&str]) -> usize {
+    if written.is_empty() {
+        return 0;
+    }
+    let existing = fs::read_to_string(file_path).unwrap_or_default();
+    let known = redact::extract_secret_matches(&existing);
+    written
+        .iter()
+        .flat_map(|text| redact::extract_secret_matches(text))
+        .filter(|m| !known.contains(m))
+        .count()
+}
+
+/// Deny or ask when the written text reproduces AMBER content disclosed
+/// earlier in the session and the target sits below AMBER.
+fn leak_check(
+    tool_input: &ToolInput,
+    file_path: &str,
+    session_id: Option<&str>,
+) -> Option<Verdict> {
+    let written = tool_input.written_text();
+    if written.is_empty() {
+        return None;
+    }
+    let leak = fingerprint::check_write(file_path, session_id, &written.join("\n"))?;
+    let reason = format!(
+        "TLP — this write reproduces AMBER content read earlier in the session \
+         ({} of {} word shingles match) in a lower-level file: {file_path}. \
+         Summarise at the target's level instead of copying.",
+        leak.overlap.matched, leak.overlap.total
+    );
+    Some(match leak.action {
+        config::LeakAction::Deny => Verdict::Deny(reason),
+        config::LeakAction::Ask => Verdict::Ask(reason),
+    })
+}
+
+/// Serve a read through redaction instead of the native tool. AMBER
+/// disclosures are fingerprinted for cross-level leak detection.
+fn redacted_read(
+    file_path: &str,
+    classification: &Classification,
+    session_id: Option<&str>,
+    preface: &str,
+) -> Option<Verdict> {
+    let content = fs::read_to_string(file_path).ok()?;
+    let mut opts = config::load(&classification.vault_root).redaction;
+    opts.reveal
+        .extend(approval::revealed(file_path, session_id, None));
+    let view = redact::safe_view_with(&content, &opts);
+    if classification.level == tlp::Tlp::Amber {
+        fingerprint::record_disclosure(session_id, &view.text);
+    }
+    Some(Verdict::Redact {
+        message: format!("{preface}\n\n{}", view.text),
+        redactions: view.redactions,
+        secrets: view.secrets,
+    })
+}
+
+/// Point the agent to `safe-write`, which edits around hidden content.
+fn use_safe_write(action: Action, file_path: &str, why: &str) -> Verdict {
+    if action == Action::Edit {
+        Verdict::Deny(format!(
+            "{why}, so native Edit is blocked. Use:\n\
+             safe-write edit \"{file_path}\" --old '<old>' --new '<new>'"
+        ))
+    } else {
+        Verdict::Deny(format!(
+            "{why}, so native Write is blocked. Use safe-write, which restores hidden \
+             content:\n\
+             cat <<'EOF' | safe-write write \"{file_path}\"\n\
+             <content from safe-read, [REDACTED#n] placeholders kept>\n\
+             EOF"
+        ))
+    }
+}
+
+/// Note printed when an action on a RED or AMBER file goes through.
+fn allow_note(action: Action, classification: &Classification) -> Option<String> {
+    let rel = &classification.rel_path;
+    match (classification.level, action) {
+        (tlp::Tlp::Red, Action::Create) => {
+            Some(format!("TLP:RED — new file creation allowed in: {rel}"))
+        }
+        (tlp::Tlp::Amber, Action::Edit | Action::Overwrite | Action::Create) => Some(format!(
+            "TLP:AMBER — editing allowed, but never output content verbatim from: {rel}"
+        )),
+        _ => None,
+    }
+}
+
+fn decide(
+    action: Action,
+    file_path: &str,
+    classification: &Classification,
+    policy: &Policy,
+    session_id: Option<&str>,
+) -> Verdict {
+    if classification.config_error {
+        let diagnostic = classification
+            .diagnostic
+            .as_deref()
+            .unwrap_or("Malformed .tlp config");
+        return Verdict::Deny(format!(
+            "{diagnostic}. All files treated as RED until fixed."
+        ));
+    }
+
+    let level = classification.level.as_str();
+    let rel = &classification.rel_path;
+
+    match policy.effect(classification.level, action) {
+        Effect::Allow => match action {
+            // Inline markers and stray secrets apply regardless of the file's level
+            Action::Read if has_hidden_content(file_path) => {
+                let preface = format!(
+                    "TLP:{level} — {rel} contains #tlp/red sections or secrets. Redacted \
+                     content follows:"
+                );
+                redacted_read(file_path, classification, session_id, &preface)
+                    .unwrap_or(Verdict::Allow(None))
+            }
+            // Edit echoes surrounding lines and old_string can probe hidden text;
+            // an overwrite drops what the agent never saw. Whatever the level.
+            Action::Edit | Action::Overwrite if has_hidden_content(file_path) => use_safe_write(
+                action,
+                file_path,
+                &format!("TLP:{level} — {rel} contains #tlp/red sections or secrets"),
+            ),
+            _ => Verdict::Allow(allow_note(action, classification)),
+        },
+        Effect::Deny => {
+            if action == Action::Read
+                && classification.level != tlp::Tlp::Red
+                && approval::is_authorized(file_path, session_id, None)
+            {
+                let preface = format!(
+                    "TLP:{level} — read approved by the user. Redacted content of {rel} \
+                     follows; never output it verbatim:"
+                );
+                if let Some(verdict) =
+                    redacted_read(file_path, classification, session_id, &preface)
+                {
+                    return verdict;
+                }
+            }
+            if classification.level == tlp::Tlp::Red {
+                Verdict::Deny(format!("TLP:RED — access blocked for: {rel}"))
+            } else if action == Action::Read {
+                Verdict::Deny(format!(
+                    "TLP:{level} — this file requires approval. Ask the user to run in their \
+                     terminal:\n\
+                     tlp approve \"{file_path}\"\n\
+                     then use:\n\
+                     safe-read \"{file_path}\""
+                ))
+            } else {
+                Verdict::Deny(format!(
+                    "TLP:{level} — {} is not allowed by the .tlp policy for: {rel}",
+                    action.as_str()
+                ))
+            }
+        }
+        Effect::Ask => Verdict::Ask(format!(
+            "TLP:{level} — the .tlp policy asks you to confirm: {} {rel}",
+            action.as_str()
+        )),
+        Effect::Redact => match action {
+            Action::Read => {
+                let preface = format!(
+                    "TLP:{level} — redacted content of {rel} follows; never output it verbatim:"
+                );
+                redacted_read(file_path, classification, session_id, &preface)
+                    .unwrap_or_else(|| Verdict::Deny(format!("TLP:{level} — cannot read {rel}")))
+            }
+            Action::Edit | Action::Overwrite => use_safe_write(
+                action,
+                file_path,
+                &format!(
+                    "TLP:{level} — the .tlp policy only allows redaction-safe writes to {rel}"
+                ),
+            ),
+            Action::Delete => Verdict::Deny(format!(
+                "TLP:{level} — delete is not allowed by the .tlp policy for: {rel}"
+            )),
+            // Search results are redacted after the fact by tlp-scan
+            Action::Create | Action::Search => Verdict::Allow(allow_note(action, classification)),
+        },
+    }
+}
+
+/// True if monitor mode may relax the verdict: a denial or question about
+/// an AMBER, GREEN or CLEAR file, or one outside any vault. RED files and
+/// malformed configs stay enforced, redacted reads stay redacted, and native
+/// edits of files with hidden content stay blocked.
+fn relaxable(
+    verdict: &Verdict,
+    action: Action,
+    file_path: &str,
+    classification: Option<&Classification>,
+) -> bool {
+    matches!(verdict, Verdict::Deny(_) | Verdict::Ask(_))
+        && classification.is_none_or(|c| c.level != tlp::Tlp::Red && !c.config_error)
+        && (action == Action::Read || !has_hidden_content(file_path))
+}
+
+/// Monitor mode: let a relaxable verdict through with a note saying what
+/// would have happened. The first line of the reason is enough. A read of a
+/// file with hidden content gets the redacted view, never the native tool.
+fn monitored(
+    verdict: &Verdict,
+    action: Action,
+    file_path: &str,
+    classification: Option<&Classification>,
+    session_id: Option<&str>,
+) -> Verdict {
+    let (would, reason) = match verdict {
+        Verdict::Ask(reason) => ("ask", reason.as_str()),
+        _ => ("deny", verdict_reason(verdict)),
+    };
+    let note = format!(
+        "TLP monitor mode — would {would}, allowed: {}",
+        reason.lines().next().unwrap_or_default()
+    );
+    if action == Action::Read && has_hidden_content(file_path) {
+        if let Some(c) = classification {
+            let preface = format!("{note}\nRedacted content follows:");
+            if let Some(redacted) = redacted_read(file_path, c, session_id, &preface) {
+                return redacted;
+            }
+        }
+    }
+    Verdict::Allow(Some(note))
+}
+
+fn verdict_reason(verdict: &Verdict) -> &str {
+    match verdict {
+        Verdict::Deny(reason) | Verdict::Ask(reason) => reason,
+        Verdict::Redact { message, .. } => message,
+        Verdict::Allow(note) => note.as_deref().unwrap_or_default(),
+        Verdict::Override { .. } => "",
+    }
+}
+
+/// Let a blocked call through if the user issued a break-glass override for
+/// the file. A one-shot override is used up here.
+fn break_glass(
+    verdict: Verdict,
+    action: Action,
+    file_path: &str,
+    classification: &Classification,
+) -> Verdict {
+    if matches!(verdict, Verdict::Allow(_)) || classification.config_error {
+        return verdict;
+    }
+    let Some(o) = breakglass::take(file_path) else {
+        return verdict;
+    };
+    let scope = if o.one_shot {
+        "This one-shot override is now used up."
+    } else {
+        "The override stays active until it expires."
+    };
+    Verdict::Override {
+        notice: format!(
+            "⚠ TLP BREAK-GLASS — the user overrode TLP:{} for {} {}: \"{}\". \
+             This call is recorded in the audit log. {scope} Never output the content \
+             verbatim.",
+            classification.level.as_str(),
+            action.as_str(),
+            classification.rel_path,
+            o.reason
+        ),
+        reason: o.reason,
+    }
+}
+
+/// Deny changes to `.tlp` files, and edits that lower a note's own
+/// classification, whatever the file's level.
+fn tamper_check(action: Action, file_path: &str, tool_input: &ToolInput) -> Option<Verdict> {
+    if tamper::is_config(file_path) && !matches!(action, Action::Read | Action::Search) {
+        return Some(Verdict::Deny(format!(
+            "TLP — .tlp files are protected: {} is not allowed on {file_path}. \
+             Ask the user to change the policy.",
+            action.as_str()
+        )));
+    }
+    if !matches!(action, Action::Edit | Action::Overwrite) {
+        return None;
+    }
+    let existing = fs::read_to_string(file_path).ok()?;
+    let why = tamper::weakening_with(
+        &existing,
+        &tool_input.resulting_content(&existing),
+        &config::for_file(file_path).redaction,
+    )?;
+    Some(Verdict::Deny(format!(
+        "TLP — this change {why} in {file_path}. Only the user may lower a \
+         classification."
+    )))
+}
+
+/// Append the decision to the audit log.
+fn log(
+    settings: &config::Settings,
+    tool_name: &str,
+    file_path: &str,
+    classification: &Classification,
+    session_id: Option<&str>,
+    verdict: &Verdict,
+    monitor: bool,
+) -> Result<(), String> {
+    let mut record = audit::Record::new(tool_name, file_path, classification, Decision::Allow);
+    record.session_id = session_id.map(String::from);
+    record.monitor = monitor;
+    match verdict {
+        Verdict::Allow(_) => {}
+        Verdict::Override { reason, .. } => {
+            record.break_glass = Some(reason.clone());
+            return audit::log_always(&settings.audit, &record).map_err(|e| e.to_string());
+        }
+        Verdict::Deny(_) => record.decision = Decision::Deny,
+        Verdict::Ask(_) => record.decision = Decision::Ask,
+        Verdict::Redact {
+            redactions,
+            secrets,
+            ..
+        } => {
+            record.decision = Decision::Redact;
+            record.redactions = *redactions;
+            record.secrets = *secrets;
+        }
+    }
+    audit::log(&settings.audit, &record);
+    Ok(())
+}
+
+/// Evaluate one file the tool call touches.
+fn check(
+    tool_name: &str,
+    action: Action,
+    file_path: &str,
+    tool_input: &ToolInput,
+    session_id: Option<&str>,
+) -> Verdict {
+    if in_state_dir(file_path) {
+        return Verdict::Deny("TLP — the TLP state directory is off-limits to file tools.".into());
+    }
+
+    let classification = tlp::classify_file(file_path);
+    let settings = classification
+        .as_ref()
+        .map(|c| config::load(&c.vault_root))
+        .unwrap_or_default();
+
+    // Enforced in monitor mode too, and never overridden
+    if let Some(verdict) = tamper_check(action, file_path, tool_input) {
+        if let Some(c) = &classification {
+            let _ = log(
+                &settings, tool_name, file_path, c, session_id, &verdict, false,
+            );
+        }
+        return verdict;
+    }
+
+    // Secrets and leaks are checked for every file, inside a vault or not
+    let secrets = introduced_secrets(file_path, &tool_input.written_text());
+    let verdict = if secrets > 0 && !settings.secrets.allows(file_path) {
+        Verdict::Deny(format!(
+            "TLP — refusing to write {secrets} secret(s) matching known credential formats \
+             into {file_path}. Reference secrets through environment variables or a secret \
+             manager instead; test fixtures can be allowlisted under secrets.allow in .tlp."
+        ))
+    } else if let Some(leak) = leak_check(tool_input, file_path, session_id) {
+        leak
+    } else if let Some(c) = &classification {
+        let verdict = decide(action, file_path, c, &settings.policy, session_id);
+        // Monitor mode lets the call through anyway; keep one-shot overrides
+        if settings.hook_monitoring() && relaxable(&verdict, action, file_path, Some(c)) {
+            verdict
+        } else {
+            break_glass(verdict, action, file_path, c)
+        }
+    } else {
+        return Verdict::Allow(None); // File outside any vault — not our problem
+    };
+
+    let monitor = settings.hook_monitoring()
+        && relaxable(&verdict, action, file_path, classification.as_ref());
+    if let Some(classification) = &classification {
+        // An override nobody can audit is no override
+        if let Err(e) = log(
+            &settings,
+            tool_name,
+            file_path,
+            classification,
+            session_id,
+            &verdict,
+            monitor,
+        ) {
+            return Verdict::Deny(format!(
+                "TLP BREAK-GLASS refused — the override could not be recorded in the audit \
+                 log ({e}). Ask the user to fix the audit log location."
+            ));
+        }
+    }
+    if monitor {
+        return monitored(
+            &verdict,
+            action,
+            file_path,
+            classification.as_ref(),
+            session_id,
+        );
+    }
+    verdict
+}
+
+/// Files the tool call touches, with the policy action for each.
+fn targets(tool_name: &str, tool_input: &ToolInput, cwd: Option<&str>) -> Vec<(Action, String)> {
+    let resolve = |path: &str| match cwd {
+        Some(cwd) if Path::new(path).is_relative() => {
+            Path::new(cwd).join(path).to_string_lossy().to_string()
+        }
+        _ => path.to_string(),
+    };
+
+    if tool_name == "Bash" {
+        let command = tool_input.command.as_deref().unwrap_or_default();
+        return policy::delete_targets(command)
+            .iter()
+            .map(|path| (Action::Delete, resolve(path)))
+            .collect();
+    }
+
+    // Grep and Glob default to the working directory
+    let path = tool_input
+        .file_path
+        .as_deref()
+        .or(tool_input.path.as_deref())
+        .or(cwd.filter(|_| matches!(tool_name, "Grep" | "Glob")));
+    let Some(path) = path.filter(|p| !p.is_empty()) else {
+        return Vec::new(); // Some tool calls legitimately have no file path
+    };
+    let path = resolve(path);
+    policy::action_for(tool_name, Path::new(&path).exists())
+        .map(|action| vec![(action, path)])
+        .unwrap_or_default()
+}
+
+/// What the hook process should do: exit code, stdout and stderr.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Response {
+    pub code: u8,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+impl Response {
+    fn allow(stdout: String) -> Self {
+        Response {
+            code: 0,
+            stdout,
+            stderr: String::new(),
+        }
+    }
+}
+
+/// Evaluate a `PreToolUse` hook payload. Exit code 2 denies the tool call,
+/// with stderr shown to the model; on exit code 0 stdout carries a note or
+/// JSON hook output.
+pub fn evaluate(payload: &str) -> Response {
+    let Ok(input) = serde_json::from_str::<HookInput>(payload) else {
+        return Response::default(); // JSON parse error is Claude Code's responsibility
+    };
+
+    let tool_name = input.tool_name.unwrap_or_default();
+    let Some(tool_input) = input.tool_input else {
+        return Response::default();
+    };
+    let session_id = input.session_id.as_deref();
+
+    if tool_name == "Bash"
+        && tamper::shell_touches_config(tool_input.command.as_deref().unwrap_or_default())
+    {
+        return Response {
+            code: 2,
+            stdout: String::new(),
+            stderr: "TLP — .tlp files are protected; this command would change one. \
+                     Ask the user to change the policy.\n"
+                .to_string(),
+        };
+    }
+
+    // The first target that isn't allowed decides; notes of allowed ones are combined
+    let mut notes = Vec::new();
+    let mut overrides = Vec::new();
+    let mut verdict = None;
+    for (action, path) in targets(&tool_name, &tool_input, input.cwd.as_deref()) {
+        match check(&tool_name, action, &path, &tool_input, session_id) {
+            Verdict::Allow(note) => notes.extend(note),
+            Verdict::Override { notice, .. } => overrides.push(notice),
+            other => {
+                verdict = Some(other);
+                break;
+            }
+        }
+    }
+    let verdict = verdict.unwrap_or_else(|| {
+        if overrides.is_empty() {
+            Verdict::Allow((!notes.is_empty()).then(|| notes.join("\n")))
+        } else {
+            Verdict::Override {
+                notice: overrides
+                    .into_iter()
+                    .chain(notes)
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+                reason: String::new(),
+            }
+        }
+    });
+
+    match verdict {
+        Verdict::Allow(note) => Response::allow(note.map(|n| format!("{n}\n")).unwrap_or_default()),
+        // Shown to the user, so the override is never silent
+        Verdict::Override { notice, .. } => Response::allow(format!(
+            "{}\n",
+            serde_json::json!({ "systemMessage": notice })
+        )),
+        Verdict::Ask(reason) => {
+            let output = serde_json::json!({
+                "hookSpecificOutput": {
+                    "hookEventName": "PreToolUse",
+                    "permissionDecision": "ask",
+                    "permissionDecisionReason": reason,
+                }
+            });
+            Response::allow(format!("{output}\n"))
+        }
+        Verdict::Deny(reason)
+        | Verdict::Redact {
+            message: reason, ..
+        } => Response {
+            code: 2,
+            stdout: String::new(),
+            stderr: format!("{reason}\n"),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,40 @@
+use super::*;
+use tempfile::tempdir;
+
+#[test]
+fn test_malformed_payload_is_allowed() {
+    assert_eq!(evaluate("not json"), Response::default());
+    assert_eq!(evaluate(r#"{"tool_name":"Read"}"#), Response::default());
+}
+
+#[test]
+fn test_red_file_is_denied() {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join(".tlp"), "RED:\n  - \"Contacts/**\"\n").unwrap();
+    let payload =
+        r#"{"tool_name":"Read","tool_input":{"file_path":"Contacts/anna.md"},"cwd":"CWD"}"#
+            .replace("CWD", &dir.path().to_string_lossy());
+
+    let response = evaluate(&payload);
+    assert_eq!(response.code, 2);
+    assert!(response.stdout.is_empty());
+    assert!(response.stderr.contains("TLP:RED"));
+}
+
+#[test]
+fn test_bash_targets_only_deletions() {
+    let input = ToolInput {
+        file_path: None,
+        path: None,
+        command: Some("cat a.md && rm -f b.md".to_string()),
+        content: None,
+        old_string: None,
+        new_string: None,
+        replace_all: None,
+        edits: None,
+    };
+    assert_eq!(
+        targets("Bash", &input, Some("/vault")),
+        vec![(Action::Delete, "/vault/b.md".to_string())]
+    );
+}
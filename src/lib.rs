@@ -1,5 +1,18 @@
 // Library crate — modules are re-exported for use by binaries in src/bin/.
+pub mod approval;
+pub mod audit;
+pub mod breakglass;
+pub mod cache;
+pub mod config;
+#[cfg(unix)]
+pub mod daemon;
+pub mod fingerprint;
 pub mod frontmatter;
+pub mod guard;
+pub mod pin;
+pub mod policy;
 pub mod redact;
+pub mod state;
+pub mod tamper;
 pub mod tlp;
 pub mod vault;
@@ -0,0 +1,179 @@
+use crate::approval::to_hex;
+use crate::cache;
+use crate::state;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Hashes of the `.tlp` files the user accepted, kept outside every vault.
+///
+/// Each pinned tree maps the `.tlp` files below its root (relative paths) to
+/// their SHA-256. Trees nobody pinned are not checked.
+#[derive(Debug, Default, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Pins {
+    pub trees: BTreeMap<String, BTreeMap<String, String>>,
+}
+
+impl Pins {
+    /// Pin the `.tlp` files scanned under `root`. Trees nested inside it are
+    /// folded in, and an enclosing tree takes the new hashes for its part
+    /// below `root` — nothing outside `root` is re-accepted.
+    pub fn pin(&mut self, root: &Path, scanned: BTreeMap<String, String>) {
+        self.trees
+            .retain(|tree, _| !Path::new(tree).starts_with(root));
+        for (tree, pinned) in &mut self.trees {
+            let Ok(below) = root.strip_prefix(tree) else {
+                continue;
+            };
+            pinned.retain(|rel, _| !Path::new(rel).starts_with(below));
+            for (rel, digest) in &scanned {
+                let rel = below.join(rel).to_string_lossy().to_string();
+                pinned.insert(rel, digest.clone());
+            }
+        }
+        self.trees
+            .insert(root.to_string_lossy().to_string(), scanned);
+    }
+}
+
+/// A pinned tree whose `.tlp` files no longer match their pins.
+#[derive(Debug, PartialEq)]
+pub struct Mismatch {
+    pub root: PathBuf,
+    pub problem: String,
+}
+
+/// Location of the pins file in the state directory.
+pub fn pins_path() -> Option<PathBuf> {
+    Some(state::state_dir()?.join("pins.json"))
+}
+
+/// Load the pins. A missing file means nothing is pinned; a file that can't
+/// be read or parsed is an error, since it may have pinned anything.
+pub fn load(path: &Path) -> io::Result<Pins> {
+    match fs::read_to_string(path) {
+        Ok(content) => serde_json::from_str(&content).map_err(io::Error::other),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Pins::default()),
+        Err(e) => Err(e),
+    }
+}
+
+pub fn save(path: &Path, pins: &Pins) -> io::Result<()> {
+    let json = serde_json::to_string_pretty(pins).map_err(io::Error::other)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let tmp = path.with_extension("json.tmp");
+    fs::write(&tmp, json)?;
+    fs::rename(&tmp, path)
+}
+
+pub fn hash(content: &[u8]) -> String {
+    to_hex(&Sha256::digest(content))
+}
+
+/// Hash of a file, from the daemon's cache when it is enabled.
+fn digest(path: &Path) -> Option<String> {
+    if let Some(digest) = cache::with(|c| c.digest(path)) {
+        return digest;
+    }
+    fs::read(path).ok().map(|content| hash(&content))
+}
+
+/// Hash every `.tlp` below a root, keyed by path relative to it.
+/// Symlinks and `.git` directories are not followed.
+pub fn scan(root: &Path) -> io::Result<BTreeMap<String, String>> {
+    let mut found = BTreeMap::new();
+    let mut dirs = vec![root.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        for entry in fs::read_dir(&dir)? {
+            let entry = entry?;
+            let kind = entry.file_type()?;
+            let path = entry.path();
+            if kind.is_dir() && entry.file_name() != ".git" {
+                dirs.push(path);
+            } else if kind.is_file() && entry.file_name() == ".tlp" {
+                let rel = path.strip_prefix(root).unwrap_or(&path);
+                found.insert(rel.to_string_lossy().to_string(), hash(&fs::read(&path)?));
+            }
+        }
+    }
+    Ok(found)
+}
+
+/// Check the pinned tree around a file: every pinned `.tlp` must still hash
+/// the same, and the `.tlp` that governs the file (at `vault_root`) must be
+/// one of them. Files outside pinned trees always pass.
+pub fn verify_with(
+    pins: &Pins,
+    abs_path: &Path,
+    vault_root: Option<&Path>,
+) -> Result<(), Mismatch> {
+    // The innermost pinned tree containing the file
+    let Some((root, pinned)) = pins
+        .trees
+        .iter()
+        .filter(|(root, _)| abs_path.starts_with(root))
+        .max_by_key(|(root, _)| root.len())
+    else {
+        return Ok(());
+    };
+    let root = PathBuf::from(root);
+    let mismatch = |problem: String| Mismatch {
+        root: root.clone(),
+        problem: format!("{problem} since `tlp pin` — run `tlp pin` to accept the change"),
+    };
+
+    for (rel, expected) in pinned {
+        match digest(&root.join(rel)) {
+            Some(digest) if digest == *expected => {}
+            Some(_) => return Err(mismatch(format!("{rel} changed"))),
+            None => return Err(mismatch(format!("{rel} was removed"))),
+        }
+    }
+
+    if let Some(vault_root) = vault_root.filter(|v| v.starts_with(&root)) {
+        let rel = vault_root.join(".tlp");
+        let rel = rel.strip_prefix(&root).unwrap_or(&rel).to_string_lossy();
+        if !pinned.contains_key(rel.as_ref()) {
+            return Err(mismatch(format!("{rel} was added")));
+        }
+    }
+    Ok(())
+}
+
+/// `verify_with` against the pins in the state directory. An unreadable
+/// pins file fails every vault file closed, since it may have pinned any
+/// vault; files outside every vault have no `.tlp` to protect.
+pub fn verify(file_path: &str, vault_root: Option<&Path>) -> Result<(), Mismatch> {
+    let Some(path) = pins_path() else {
+        return Ok(());
+    };
+    let abs = |p: &Path| std::path::absolute(p).unwrap_or_else(|_| p.to_path_buf());
+    let pins =
+        cache::with(|c| c.pins(&path)).unwrap_or_else(|| load(&path).map_err(|e| e.to_string()));
+    let pins = match (pins, vault_root) {
+        (Ok(pins), _) => pins,
+        (Err(_), None) => return Ok(()),
+        (Err(e), Some(vault_root)) => {
+            return Err(Mismatch {
+                root: abs(vault_root),
+                problem: format!(
+                    "cannot read the pins file {}: {e} — fix or remove it, then run `tlp pin`",
+                    path.display()
+                ),
+            })
+        }
+    };
+    verify_with(
+        &pins,
+        &abs(Path::new(file_path)),
+        vault_root.map(abs).as_deref(),
+    )
+}
+
+#[cfg(test)]
+mod tests;
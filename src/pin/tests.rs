@@ -0,0 +1,97 @@
+use super::*;
+use tempfile::tempdir;
+
+fn vault() -> tempfile::TempDir {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join(".tlp"), "RED:\n  - \"secret/**\"\n").unwrap();
+    fs::create_dir_all(dir.path().join("sub/.git")).unwrap();
+    fs::write(dir.path().join("sub/.tlp"), "GREEN:\n  - \"**\"\n").unwrap();
+    fs::write(dir.path().join("sub/.git/.tlp"), "ignored").unwrap();
+    fs::write(dir.path().join("note.md"), "hello").unwrap();
+    dir
+}
+
+fn pinned(root: &Path) -> Pins {
+    let mut pins = Pins::default();
+    pins.pin(root, scan(root).unwrap());
+    pins
+}
+
+#[test]
+fn test_scan_finds_tlp_files_outside_git() {
+    let dir = vault();
+    let found = scan(dir.path()).unwrap();
+    let keys: Vec<&str> = found.keys().map(String::as_str).collect();
+    assert_eq!(keys, [".tlp", "sub/.tlp"]);
+    assert_eq!(found[".tlp"], hash(b"RED:\n  - \"secret/**\"\n"));
+}
+
+#[test]
+fn test_verify_passes_unchanged_and_unpinned_trees() {
+    let dir = vault();
+    let pins = pinned(dir.path());
+    let note = dir.path().join("note.md");
+    assert_eq!(verify_with(&pins, &note, Some(dir.path())), Ok(()));
+
+    let other = tempdir().unwrap();
+    fs::write(other.path().join(".tlp"), "").unwrap();
+    let file = other.path().join("a.md");
+    assert_eq!(verify_with(&pins, &file, Some(other.path())), Ok(()));
+}
+
+#[test]
+fn test_verify_detects_changed_removed_and_added_tlp() {
+    let dir = vault();
+    let pins = pinned(dir.path());
+    let note = dir.path().join("note.md");
+
+    fs::write(dir.path().join("sub/.tlp"), "CLEAR:\n  - \"**\"\n").unwrap();
+    let err = verify_with(&pins, &note, Some(dir.path())).unwrap_err();
+    assert_eq!(err.root, dir.path());
+    assert!(err.problem.starts_with("sub/.tlp changed"));
+
+    fs::remove_file(dir.path().join("sub/.tlp")).unwrap();
+    let err = verify_with(&pins, &note, Some(dir.path())).unwrap_err();
+    assert!(err.problem.starts_with("sub/.tlp was removed"));
+
+    let pins = pinned(dir.path());
+    fs::create_dir_all(dir.path().join("new")).unwrap();
+    fs::write(dir.path().join("new/.tlp"), "").unwrap();
+    let file = dir.path().join("new/a.md");
+    let err = verify_with(&pins, &file, Some(&dir.path().join("new"))).unwrap_err();
+    assert!(err.problem.starts_with("new/.tlp was added"));
+}
+
+#[test]
+fn test_pin_subtree_updates_enclosing_tree_only_below_it() {
+    let dir = vault();
+    let mut pins = pinned(dir.path());
+    fs::write(dir.path().join(".tlp"), "").unwrap();
+    fs::write(dir.path().join("sub/.tlp"), "").unwrap();
+
+    // Re-pinning sub/ accepts its change, but not the change above it
+    let sub = dir.path().join("sub");
+    pins.pin(&sub, scan(&sub).unwrap());
+    let file = sub.join("a.md");
+    assert_eq!(verify_with(&pins, &file, Some(&sub)), Ok(()));
+    let err = verify_with(&pins, &dir.path().join("note.md"), Some(dir.path())).unwrap_err();
+    assert!(err.problem.starts_with(".tlp changed"));
+
+    // Pinning the whole vault folds the nested tree in
+    pins.pin(dir.path(), scan(dir.path()).unwrap());
+    assert_eq!(pins.trees.len(), 1);
+}
+
+#[test]
+fn test_load_missing_is_empty_and_corrupt_is_error() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("pins.json");
+    assert_eq!(load(&path).unwrap(), Pins::default());
+
+    let pins = pinned(vault().path());
+    save(&path, &pins).unwrap();
+    assert_eq!(load(&path).unwrap(), pins);
+
+    fs::write(&path, "{not json").unwrap();
+    assert!(load(&path).is_err());
+}
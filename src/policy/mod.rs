@@ -0,0 +1,140 @@
+use crate::tlp::{self, Tlp};
+use serde::Deserialize;
+use std::collections::HashMap;
+
+/// What a tool call does to a file, as named in the `.tlp` policy matrix.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Action {
+    Read,
+    Edit,
+    Overwrite,
+    Create,
+    Delete,
+    Search,
+}
+
+impl Action {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Action::Read => "read",
+            Action::Edit => "edit",
+            Action::Overwrite => "overwrite",
+            Action::Create => "create",
+            Action::Delete => "delete",
+            Action::Search => "search",
+        }
+    }
+}
+
+/// What the policy does with an action.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Effect {
+    Allow,
+    Deny,
+    /// Let the user confirm the tool call.
+    Ask,
+    /// Only through redaction: reads get the `safe-read` view, edits and
+    /// overwrites must go through `safe-write`, deletes are refused.
+    Redact,
+}
+
+/// Level × action matrix. Cells not listed keep the built-in defaults.
+///
+/// ```yaml
+/// policy:
+///   AMBER:
+///     edit: deny
+///   RED:
+///     create: deny
+/// ```
+///
+/// Level names are case-insensitive; an unknown one (`AMBR:`) is an error,
+/// like an unknown action or effect, so the `.tlp` counts as malformed.
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(try_from = "HashMap<String, HashMap<Action, Effect>>")]
+pub struct Policy(HashMap<Tlp, HashMap<Action, Effect>>);
+
+impl TryFrom<HashMap<String, HashMap<Action, Effect>>> for Policy {
+    type Error = String;
+
+    fn try_from(rows: HashMap<String, HashMap<Action, Effect>>) -> Result<Self, String> {
+        rows.into_iter()
+            .map(|(name, row)| match tlp::from_str(&name) {
+                Some(level) => Ok((level, row)),
+                None => Err(format!(
+                    "unknown policy level `{name}`, expected RED, AMBER, GREEN or CLEAR"
+                )),
+            })
+            .collect::<Result<_, _>>()
+            .map(Policy)
+    }
+}
+
+impl Policy {
+    /// Effect for a level and action: the `.tlp` cell, else the default.
+    pub fn effect(&self, level: Tlp, action: Action) -> Effect {
+        self.0
+            .get(&level)
+            .and_then(|row| row.get(&action).copied())
+            .unwrap_or_else(|| default_effect(level, action))
+    }
+}
+
+/// Built-in policy: RED only allows creating new files, AMBER requires
+/// approval to read, GREEN and CLEAR allow everything.
+pub fn default_effect(level: Tlp, action: Action) -> Effect {
+    match (level, action) {
+        (Tlp::Red, Action::Create) => Effect::Allow,
+        (Tlp::Red, _) | (Tlp::Amber, Action::Read) => Effect::Deny,
+        _ => Effect::Allow,
+    }
+}
+
+/// Map a file tool to a policy action. None for tools the policy doesn't cover.
+pub fn action_for(tool_name: &str, target_exists: bool) -> Option<Action> {
+    match tool_name {
+        "Read" => Some(Action::Read),
+        "Edit" | "MultiEdit" => Some(Action::Edit),
+        "Write" if target_exists => Some(Action::Overwrite),
+        "Write" => Some(Action::Create),
+        "Grep" | "Glob" => Some(Action::Search),
+        _ => None,
+    }
+}
+
+/// Paths removed by `rm`, `unlink` or `git rm` in a shell command.
+///
+/// Best effort: splits on `;`, `&&`, `||`, `|` and newlines, strips simple
+/// quotes, and skips flags. Globs and variables are returned unexpanded.
+pub fn delete_targets(command: &str) -> Vec<String> {
+    command
+        .split(['\n', ';', '|', '&'])
+        .flat_map(|part| {
+            let words: Vec<&str> = part.split_whitespace().collect();
+            let args = match words.as_slice() {
+                ["rm" | "unlink", rest @ ..] | ["git", "rm", rest @ ..] => rest.to_vec(),
+                _ => Vec::new(),
+            };
+            let mut after_dashes = false;
+            args.into_iter()
+                .filter(move |arg| {
+                    if after_dashes {
+                        return true;
+                    }
+                    if *arg == "--" {
+                        after_dashes = true;
+                        return false;
+                    }
+                    !arg.starts_with('-')
+                })
+                .map(|arg| arg.trim_matches(['"', '\'']).to_string())
+                .collect::<Vec<_>>()
+        })
+        .filter(|target| !target.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,57 @@
+use super::*;
+
+fn parse(yaml: &str) -> Policy {
+    serde_yaml::from_str(yaml).unwrap()
+}
+
+#[test]
+fn test_defaults_match_built_in_levels() {
+    let policy = Policy::default();
+    assert_eq!(policy.effect(Tlp::Red, Action::Read), Effect::Deny);
+    assert_eq!(policy.effect(Tlp::Red, Action::Create), Effect::Allow);
+    assert_eq!(policy.effect(Tlp::Red, Action::Search), Effect::Deny);
+    assert_eq!(policy.effect(Tlp::Amber, Action::Read), Effect::Deny);
+    assert_eq!(policy.effect(Tlp::Amber, Action::Edit), Effect::Allow);
+    assert_eq!(policy.effect(Tlp::Green, Action::Delete), Effect::Allow);
+    assert_eq!(policy.effect(Tlp::Clear, Action::Read), Effect::Allow);
+}
+
+#[test]
+fn test_cells_override_defaults() {
+    let policy = parse("AMBER:\n  edit: deny\n  read: redact\nred:\n  create: ask\n");
+    assert_eq!(policy.effect(Tlp::Amber, Action::Edit), Effect::Deny);
+    assert_eq!(policy.effect(Tlp::Amber, Action::Read), Effect::Redact);
+    assert_eq!(policy.effect(Tlp::Amber, Action::Create), Effect::Allow);
+    assert_eq!(policy.effect(Tlp::Red, Action::Create), Effect::Ask);
+    assert_eq!(policy.effect(Tlp::Red, Action::Read), Effect::Deny);
+}
+
+#[test]
+fn test_unknown_effect_is_rejected() {
+    assert!(serde_yaml::from_str::<Policy>("AMBER:\n  edit: maybe\n").is_err());
+}
+
+#[test]
+fn test_unknown_level_is_rejected() {
+    assert!(serde_yaml::from_str::<Policy>("AMBR:\n  edit: deny\n").is_err());
+}
+
+#[test]
+fn test_action_for_tools() {
+    assert_eq!(action_for("Read", true), Some(Action::Read));
+    assert_eq!(action_for("MultiEdit", true), Some(Action::Edit));
+    assert_eq!(action_for("Write", true), Some(Action::Overwrite));
+    assert_eq!(action_for("Write", false), Some(Action::Create));
+    assert_eq!(action_for("Grep", true), Some(Action::Search));
+    assert_eq!(action_for("WebFetch", false), None);
+}
+
+#[test]
+fn test_delete_targets() {
+    assert_eq!(delete_targets("rm -f a.md \"b.md\""), vec!["a.md", "b.md"]);
+    assert_eq!(
+        delete_targets("cd notes && git rm -r old/ ; unlink -- -odd.md"),
+        vec!["old/", "-odd.md"]
+    );
+    assert!(delete_targets("ls -la && echo rm").is_empty());
+}
@@ -1,7 +1,12 @@
 const TLP_RED_MARKER: &str = "#tlp/red";
-const TLP_BOUNDARY_TAGS: &[&str] = &["#tlp/amber", "#tlp/green", "#tlp/clear"];
 
+use crate::config::{Pii, Redaction, Syntax};
+use crate::tlp::Tlp;
 use regex::Regex;
+use std::collections::BTreeMap;
+use std::iter::Peekable;
+use std::ops::Range;
+use std::str::Lines;
 use std::sync::OnceLock;
 
 /// Secret detection patterns curated from [gitleaks](https://github.com/gitleaks/gitleaks).
@@ -69,7 +74,22 @@ const SECRET_PATTERNS: &str = concat!(
     "|", r"CFPAT-[a-zA-Z0-9_-]{43}",                       // Contentful PAT
     // Encryption
     "|", r"AGE-SECRET-KEY-1[qpzry9x8gf2tvdw0s3jn54khce6mua7l]{58}", // age secret key
-    "|", r"-----BEGIN[A-Z ]*PRIVATE KEY-----",              // PEM private key header
+    // Tokens
+    "|", r"eyJ[A-Za-z0-9_-]{10,}\.eyJ[A-Za-z0-9_-]{10,}\.[A-Za-z0-9_-]{10,}", // JWT
+);
+
+/// Private key blocks spanning lines: PEM (RSA, EC, PKCS#8…), OpenSSH and
+/// PGP. An unterminated block runs to the end of the content — fail-safe.
+const KEY_BLOCK_PATTERN: &str = concat!(
+    r"(?s)-----BEGIN [A-Z0-9 ]*PRIVATE KEY(?: BLOCK)?-----",
+    r".*?(?:-----END [A-Z0-9 ]*PRIVATE KEY(?: BLOCK)?-----|\z)",
+);
+
+/// A YAML key whose name suggests a secret, introducing a `|` or `>` block
+/// scalar. The indented lines below it are the value.
+const YAML_SECRET_KEY_PATTERN: &str = concat!(
+    r"(?i)^(\s*)(?:- )?[\w.-]*(?:secret|password|passwd|passphrase|token|credential|",
+    r"private[_-]?key|api[_-]?key)s?[\w.-]*\s*:\s*[|>][-+0-9]*\s*$",
 );
 
 /// Compiled regex for secret detection.
@@ -78,244 +98,984 @@ fn secret_regex() -> &'static Regex {
     RE.get_or_init(|| Regex::new(SECRET_PATTERNS).expect("secret patterns must compile"))
 }
 
-/// All TLP markers (red + boundary tags), used for inline detection.
-const ALL_TLP_MARKERS: &[&str] = &["#tlp/red", "#tlp/amber", "#tlp/green", "#tlp/clear"];
+fn key_block_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(KEY_BLOCK_PATTERN).expect("key block pattern must compile"))
+}
 
-/// Check if a trimmed line is a TLP boundary tag (not #tlp/red, which starts sections).
-fn is_tlp_boundary(trimmed: &str) -> bool {
-    TLP_BOUNDARY_TAGS.contains(&trimmed)
+fn yaml_secret_key_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(YAML_SECRET_KEY_PATTERN).expect("YAML secret key pattern must compile")
+    })
 }
 
-/// Strip content between #tlp/red and any other #tlp/* boundary marker.
-/// Each RED section is replaced with a single [REDACTED] line.
-///
-/// Supports two modes:
-/// - **Block mode**: `#tlp/red` alone on a line starts a multi-line redacted section,
-///   ended by any `#tlp/*` boundary tag alone on a line.
-/// - **Inline mode**: `#tlp/red` mid-line redacts from the marker to the next
-///   `#tlp/*` boundary tag on the same line, or to end of line if none found.
-pub fn redact_tlp_sections(content: &str) -> String {
-    let mut result = Vec::new();
-    let mut in_redacted = false;
-    let mut redaction_emitted = false;
+/// Byte ranges of every secret in the content, in document order and
+/// non-overlapping. Multi-line spans (key blocks, YAML block scalars) are
+/// found first; single-line patterns inside them are not counted again.
+fn secret_spans(content: &str) -> Vec<Range<usize>> {
+    let mut spans: Vec<Range<usize>> = key_block_regex()
+        .find_iter(content)
+        .map(|m| m.range())
+        .collect();
+    spans.extend(yaml_block_secrets(content));
 
-    for line in content.lines() {
-        let trimmed = line.trim();
+    for m in secret_regex().find_iter(content) {
+        spans.push(m.range());
+    }
+    non_overlapping(spans)
+}
 
-        // Block mode: whole-line #tlp/red starts multi-line redaction
-        if trimmed == TLP_RED_MARKER {
-            if !in_redacted {
-                in_redacted = true;
-                redaction_emitted = false;
-            }
-            continue;
+/// Sort spans earliest first, longest first on ties, and drop anything
+/// overlapping a kept span.
+fn non_overlapping(mut spans: Vec<Range<usize>>) -> Vec<Range<usize>> {
+    spans.sort_by_key(|r| (r.start, std::cmp::Reverse(r.end)));
+    let mut kept: Vec<Range<usize>> = Vec::new();
+    for span in spans {
+        if kept.last().is_none_or(|last| span.start >= last.end) {
+            kept.push(span);
         }
+    }
+    kept
+}
 
-        // Block mode: whole-line boundary tag ends multi-line redaction
-        if in_redacted && is_tlp_boundary(trimmed) {
-            if !redaction_emitted {
-                result.push("[REDACTED]".to_string());
-            }
-            in_redacted = false;
-            continue;
-        }
+/// Values of YAML block scalars under secret-looking keys: from the first
+/// non-blank character of the first value line to the end of the last
+/// non-blank one, so the placeholder keeps the value's indentation.
+fn yaml_block_secrets(content: &str) -> Vec<Range<usize>> {
+    let re = yaml_secret_key_regex();
+    let mut spans = Vec::new();
+    let mut lines = line_ranges(content).peekable();
 
-        if in_redacted {
-            if !redaction_emitted {
-                result.push("[REDACTED]".to_string());
-                redaction_emitted = true;
+    while let Some((start, end)) = lines.next() {
+        let Some(caps) = re.captures(&content[start..end]) else {
+            continue;
+        };
+        let key_indent = caps[1].len();
+        let mut value: Option<Range<usize>> = None;
+        while let Some(&(s, e)) = lines.peek() {
+            let line = &content[s..e];
+            let trimmed = line.trim_start();
+            if !trimmed.is_empty() && line.len() - trimmed.len() <= key_indent {
+                break;
             }
-        } else {
-            // Inline mode: check for #tlp/red mid-line
-            result.push(redact_inline_markers(line));
+            if !trimmed.is_empty() {
+                let trimmed_end = s + line.trim_end().len();
+                match &mut value {
+                    Some(v) => v.end = trimmed_end,
+                    None => value = Some(e - trimmed.len()..trimmed_end),
+                }
+            }
+            lines.next();
         }
+        spans.extend(value);
     }
+    spans
+}
 
-    // Handle unterminated RED block
-    if in_redacted && !redaction_emitted {
-        result.push("[REDACTED]".to_string());
-    }
+/// `(start, end)` byte offsets of each line, excluding the line break.
+fn line_ranges(content: &str) -> impl Iterator<Item = (usize, usize)> + '_ {
+    let mut offset = 0;
+    content.split_inclusive('\n').map(move |line| {
+        let start = offset;
+        offset += line.len();
+        let body = line.trim_end_matches('\n').trim_end_matches('\r');
+        (start, start + body.len())
+    })
+}
 
-    let mut output = result.join("\n");
-    if content.ends_with('\n') {
-        output.push('\n');
+/// Replace each secret span with the placeholder `mark` yields for it.
+fn replace_secrets(content: &str, mark: &mut dyn FnMut() -> String) -> (String, usize) {
+    let spans = secret_spans(content);
+    (replace_spans(content, &spans, mark), spans.len())
+}
+
+/// Replace each span with the placeholder `mark` yields for it.
+fn replace_spans(
+    content: &str,
+    spans: &[Range<usize>],
+    mark: &mut dyn FnMut() -> String,
+) -> String {
+    let mut output = String::with_capacity(content.len());
+    let mut last = 0;
+    for span in spans {
+        output.push_str(&content[last..span.start]);
+        output.push_str(&mark());
+        last = span.end;
     }
+    output.push_str(&content[last..]);
     output
 }
 
-/// Process a single line for inline `#tlp/red` markers.
-/// Redacts from each `#tlp/red` to the next `#tlp/*` boundary tag, or to end of line.
-fn redact_inline_markers(line: &str) -> String {
-    if !line.contains(TLP_RED_MARKER) {
-        return line.to_string();
+// ─── Personal data ───
+//
+// Opt-in detectors, enabled per kind in `.tlp`. Patterns find candidates;
+// checksums (mod-97 for IBANs, Luhn for cards and SINs) and digit counts
+// weed out order numbers, dates and other look-alikes.
+
+/// A candidate pattern for one kind of personal data, and its validation.
+struct Detector {
+    kind: Pii,
+    regex: Regex,
+    valid: fn(&str) -> bool,
+}
+
+#[rustfmt::skip]
+fn pii_detectors() -> &'static [Detector] {
+    static DETECTORS: OnceLock<Vec<Detector>> = OnceLock::new();
+    DETECTORS.get_or_init(|| {
+        let detector = |kind, pattern: &str, valid| Detector {
+            kind,
+            regex: Regex::new(pattern).expect("PII pattern must compile"),
+            valid,
+        };
+        vec![
+            detector(Pii::Email, r"(?i)[a-z0-9._%+-]+@[a-z0-9](?:[a-z0-9-]*[a-z0-9])?(?:\.[a-z0-9](?:[a-z0-9-]*[a-z0-9])?)*\.[a-z]{2,}", |_| true),
+            // E.164, optionally grouped: +44 20 7946 0958
+            detector(Pii::Phone, r"\+\d{1,3}(?:[ .-]?\(?\d{1,4}\)?){2,6}", |m| (8..=15).contains(&digit_count(m))),
+            // National, with a trunk prefix and separators: 020 7946 0958, (06) 12 34 56 78.
+            // Bare digit runs such as 0123456789 are more often ids than numbers
+            detector(Pii::Phone, r"(?:\(0\d{1,4}\) ?|0\d{1,4}[ .-])\d{2,4}(?:[ .-]\d{2,4}){1,3}", |m| (9..=12).contains(&digit_count(m))),
+            // North American: (555) 010-0199
+            detector(Pii::Phone, r"\(?[2-9]\d{2}\)?[ .-]\d{3}[ .-]\d{4}", |_| true),
+            detector(Pii::Iban, r"[A-Z]{2}\d{2}(?: ?[A-Z0-9]{4}){2,7}(?: ?[A-Z0-9]{1,3})?", iban_checksum),
+            detector(Pii::Card, r"\d(?:[ -]?\d){12,18}", card_number),
+            // US Social Security Number: 123-45-6789
+            detector(Pii::NationalId, r"\d{3}-\d{2}-\d{4}", ssn),
+            // UK National Insurance number: AB 12 34 56 C
+            detector(Pii::NationalId, r"[A-CEGHJ-PR-TW-Z][A-CEGHJ-NPR-TW-Z] ?\d{2} ?\d{2} ?\d{2} ?[A-D]", nino),
+            // Canadian Social Insurance Number: 046 454 286
+            detector(Pii::NationalId, r"\d{3}[ -]\d{3}[ -]\d{3}", luhn),
+        ]
+    })
+}
+
+fn digit_count(text: &str) -> usize {
+    text.chars().filter(char::is_ascii_digit).count()
+}
+
+/// Luhn checksum over the digits of `text`.
+fn luhn(text: &str) -> bool {
+    let sum: u32 = text
+        .chars()
+        .rev()
+        .filter_map(|c| c.to_digit(10))
+        .enumerate()
+        .map(|(i, d)| match (i % 2, d * 2) {
+            (0, _) => d,
+            (_, doubled) if doubled > 9 => doubled - 9,
+            (_, doubled) => doubled,
+        })
+        .sum();
+    sum.is_multiple_of(10)
+}
+
+/// 13 to 19 digits from a card network's range that pass the Luhn check.
+fn card_number(text: &str) -> bool {
+    let network = text.starts_with(['2', '3', '4', '5', '6']);
+    network && (13..=19).contains(&digit_count(text)) && luhn(text)
+}
+
+/// IBAN mod-97 check: the country code and check digits moved to the end,
+/// letters as 10–35, leave a remainder of 1.
+fn iban_checksum(text: &str) -> bool {
+    let compact: String = text.chars().filter(|c| !c.is_whitespace()).collect();
+    if !(15..=34).contains(&compact.len()) {
+        return false;
+    }
+    let mut remainder = 0;
+    for c in compact[4..].chars().chain(compact[..4].chars()) {
+        let Some(value) = c.to_digit(36) else {
+            return false;
+        };
+        let shift = if value >= 10 { 100 } else { 10 };
+        remainder = (remainder * shift + value) % 97;
     }
+    remainder == 1
+}
 
-    let mut result = String::new();
-    let mut remaining = line;
+/// SSNs never use area 000, 666 or 900–999, group 00 or serial 0000.
+fn ssn(text: &str) -> bool {
+    let (area, rest) = text.split_at(3);
+    let (group, serial) = (&rest[1..3], &rest[4..]);
+    !matches!(area, "000" | "666") && !area.starts_with('9') && group != "00" && serial != "0000"
+}
 
-    while let Some(red_pos) = remaining.find(TLP_RED_MARKER) {
-        // Keep everything before the marker
-        result.push_str(&remaining[..red_pos]);
+/// National Insurance numbers never start with BG, GB, KN, NK, NT, TN or ZZ.
+fn nino(text: &str) -> bool {
+    !matches!(&text[..2], "BG" | "GB" | "KN" | "NK" | "NT" | "TN" | "ZZ")
+}
 
-        // Skip past the #tlp/red marker
-        let after_marker = &remaining[red_pos + TLP_RED_MARKER.len()..];
+/// True if the match is not glued to a surrounding word or number.
+fn standalone(content: &str, span: &Range<usize>) -> bool {
+    let glued = |c: char| c.is_alphanumeric() || c == '_';
+    !content[..span.start].chars().next_back().is_some_and(glued)
+        && !content[span.end..].chars().next().is_some_and(glued)
+}
 
-        // Find the closest boundary tag on this line
-        let mut closest: Option<(usize, usize)> = None; // (position, tag_len)
-        for &tag in ALL_TLP_MARKERS {
-            if tag == TLP_RED_MARKER {
-                continue; // Don't match another #tlp/red as a boundary
+/// Byte ranges of the personal data `.tlp` asked to detect, in document
+/// order and non-overlapping.
+fn pii_spans(content: &str, opts: &Redaction) -> Vec<Range<usize>> {
+    if opts.pii.is_empty() {
+        return Vec::new();
+    }
+    let spans = pii_detectors()
+        .iter()
+        .filter(|d| opts.detects(d.kind))
+        .flat_map(|d| {
+            d.regex
+                .find_iter(content)
+                .filter(|m| standalone(content, &m.range()) && (d.valid)(m.as_str()))
+                .map(|m| m.range())
+        })
+        .collect();
+    non_overlapping(spans)
+}
+
+/// Every TLP marker and the level it gives the section it opens.
+const TLP_TAGS: &[(&str, Tlp)] = &[
+    ("#tlp/red", Tlp::Red),
+    ("#tlp/amber", Tlp::Amber),
+    ("#tlp/green", Tlp::Green),
+    ("#tlp/clear", Tlp::Clear),
+];
+
+/// Red sections narrowed to a compartment: `#tlp/red/health`.
+const TLP_COMPARTMENT_PREFIX: &str = "#tlp/red/";
+
+/// Whether a trimmed line is a TLP tag alone, and if so whether its section is hidden.
+fn line_tag(trimmed: &str, opts: &Redaction) -> Option<bool> {
+    match line_markers(trimmed, opts).as_slice() {
+        [m] if m.at == 0 && m.len == trimmed.len() => Some(m.hidden),
+        _ => None,
+    }
+}
+
+/// True if a trimmed line is a TLP tag alone whose section this audience may not see.
+fn opens_hidden(trimmed: &str, opts: &Redaction) -> bool {
+    line_tag(trimmed, opts) == Some(true)
+}
+
+/// True if a trimmed line is a TLP tag alone whose section this audience may see.
+fn closes_hidden(trimmed: &str, opts: &Redaction) -> bool {
+    line_tag(trimmed, opts) == Some(false)
+}
+
+// ─── Marker tokenizer ───
+//
+// Markers are Obsidian tags: `#tlp/reddit` and `#tlp/redaction-policy` are
+// other tags, not `#tlp/red`. Unless `.tlp` says otherwise, markers inside
+// code fences and inline code are examples, not markers.
+
+/// Characters that continue a tag.
+fn is_tag_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | '-' | '/')
+}
+
+/// Byte offsets where `tag` occurs as a whole tag: not glued to a preceding
+/// word and not continued by further tag characters.
+fn tag_positions<'a>(text: &'a str, tag: &'a str) -> impl Iterator<Item = usize> + 'a {
+    text.match_indices(tag).map(|(i, _)| i).filter(move |&i| {
+        let before = text[..i].chars().next_back();
+        let after = text[i + tag.len()..].chars().next();
+        !before.is_some_and(|c| is_tag_char(c) || c == '#') && !after.is_some_and(is_tag_char)
+    })
+}
+
+/// Byte offsets and names of `#tlp/red/<compartment>` tags. The name runs
+/// to the end of the tag, so `#tlp/red/health/dental` is `health/dental`.
+fn compartment_tags(text: &str) -> impl Iterator<Item = (usize, &str)> + '_ {
+    text.match_indices(TLP_COMPARTMENT_PREFIX)
+        .filter_map(|(i, prefix)| {
+            let before = text[..i].chars().next_back();
+            if before.is_some_and(|c| is_tag_char(c) || c == '#') {
+                return None;
             }
-            if let Some(pos) = after_marker.find(tag) {
-                match closest {
-                    None => closest = Some((pos, tag.len())),
-                    Some((prev, _)) if pos < prev => closest = Some((pos, tag.len())),
-                    _ => {}
+            let rest = &text[i + prefix.len()..];
+            let name = &rest[..rest.find(|c| !is_tag_char(c)).unwrap_or(rest.len())];
+            (!name.is_empty()).then_some((i, name))
+        })
+}
+
+/// Byte ranges of inline code spans: a run of backticks up to the next run
+/// of the same length. An unmatched run is literal text.
+fn code_spans(line: &str) -> Vec<Range<usize>> {
+    let bytes = line.as_bytes();
+    let run_at = |i: usize| bytes[i..].iter().take_while(|&&b| b == b'`').count();
+    let mut spans = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] != b'`' {
+            i += 1;
+            continue;
+        }
+        let run = run_at(i);
+        let mut j = i + run;
+        let mut close = None;
+        while j < bytes.len() {
+            if bytes[j] == b'`' {
+                let r = run_at(j);
+                if r == run {
+                    close = Some(j + r);
+                    break;
                 }
+                j += r;
+            } else {
+                j += 1;
             }
         }
-
-        result.push_str("[REDACTED]");
-
-        match closest {
-            Some((pos, tag_len)) => {
-                remaining = &after_marker[pos + tag_len..];
-            }
-            None => {
-                // No boundary — redact to end of line
-                remaining = "";
+        match close {
+            Some(end) => {
+                spans.push(i..end);
+                i = end;
             }
+            None => i += run,
         }
     }
+    spans
+}
 
-    result.push_str(remaining);
-    result
+/// A `#tlp/*` tag on a line.
+struct Marker<'a> {
+    at: usize,
+    len: usize,
+    /// Marks a level above the audience, in a compartment not revealed.
+    hidden: bool,
+    compartment: Option<&'a str>,
 }
 
-// ─── Extraction (inverse of redaction, used by safe-write) ───
+/// The markers on a line, in order.
+fn line_markers<'a>(line: &'a str, opts: &Redaction) -> Vec<Marker<'a>> {
+    if !line.contains("#tlp/") {
+        return Vec::new();
+    }
+    let code = if opts.ignores_code() {
+        code_spans(line)
+    } else {
+        Vec::new()
+    };
+    let mut markers: Vec<Marker> = TLP_TAGS
+        .iter()
+        .flat_map(|&(tag, level)| {
+            tag_positions(line, tag).map(move |at| Marker {
+                at,
+                len: tag.len(),
+                hidden: !opts.audience.sees(level),
+                compartment: None,
+            })
+        })
+        .chain(compartment_tags(line).map(|(at, name)| Marker {
+            at,
+            len: TLP_COMPARTMENT_PREFIX.len() + name.len(),
+            hidden: !opts.audience.sees(Tlp::Red) && !opts.reveals(name),
+            compartment: Some(name),
+        }))
+        .filter(|m| !code.iter().any(|span| span.contains(&m.at)))
+        .collect();
+    markers.sort_by_key(|m| m.at);
+    markers
+}
 
-/// Redaction marker that replaces #tlp/red blocks in safe-read output.
-pub const REDACTED_MARKER: &str = "[REDACTED]";
+/// True if the line carries a marker above the audience — `#tlp/red` by default.
+fn has_hidden(line: &str, opts: &Redaction) -> bool {
+    line_markers(line, opts).iter().any(|m| m.hidden)
+}
 
-/// Redaction marker that replaces secret patterns in safe-read output.
-pub const SECRET_MARKER: &str = "[SECRET REDACTED]";
+/// Byte ranges of the hidden inline spans on a line: from each hidden marker
+/// to the end of the next marker the audience may see, or to the end of the
+/// line if none.
+fn hidden_spans(line: &str, opts: &Redaction) -> Vec<Range<usize>> {
+    let markers = line_markers(line, opts);
+    let mut spans = Vec::new();
+    let mut k = 0;
+    while let Some(start) = markers[k..].iter().position(|m| m.hidden).map(|p| k + p) {
+        let from = markers[start].at;
+        let Some(p) = markers[start + 1..].iter().position(|m| !m.hidden) else {
+            spans.push(from..line.len());
+            break;
+        };
+        let end = &markers[start + 1 + p];
+        spans.push(from..end.at + end.len);
+        k = start + 2 + p;
+    }
+    spans
+}
 
-/// Extract the original content of each #tlp/red block, in document order.
-/// Each entry includes the markers and all hidden lines — exactly what was
-/// replaced by a single `[REDACTED]` in `redact_tlp_sections`.
-pub fn extract_tlp_blocks(content: &str) -> Vec<String> {
-    let mut blocks = Vec::new();
-    let mut in_redacted = false;
-    let mut current_block = Vec::<String>::new();
+/// Byte offsets of each `%%` on a line, outside inline code when code is ignored.
+fn comment_delimiters(line: &str, opts: &Redaction) -> Vec<usize> {
+    if !opts.enables(Syntax::Comments) || !line.contains("%%") {
+        return Vec::new();
+    }
+    let code = if opts.ignores_code() {
+        code_spans(line)
+    } else {
+        Vec::new()
+    };
+    line.match_indices("%%")
+        .map(|(i, _)| i)
+        .filter(|i| !code.iter().any(|span| span.contains(i)))
+        .collect()
+}
 
-    for line in content.lines() {
-        let trimmed = line.trim();
+/// Byte ranges of everything hidden inline on a line: marked spans and,
+/// when enabled, `%%comments%%` and listed field values. Overlapping ranges
+/// are merged into one.
+fn inline_spans(line: &str, opts: &Redaction) -> Vec<Range<usize>> {
+    let mut spans = hidden_spans(line, opts);
+    let delimiters = comment_delimiters(line, opts);
+    spans.extend(delimiters.chunks_exact(2).map(|pair| pair[0]..pair[1] + 2));
+    spans.extend(inline_field_values(line, opts));
+    spans.sort_by_key(|span| span.start);
 
-        if trimmed == TLP_RED_MARKER && !in_redacted {
-            in_redacted = true;
-            current_block.clear();
-            current_block.push(line.to_string());
-            continue;
+    let mut merged: Vec<Range<usize>> = Vec::new();
+    for span in spans {
+        match merged.last_mut() {
+            Some(last) if span.start < last.end => last.end = last.end.max(span.end),
+            _ => merged.push(span),
         }
+    }
+    merged
+}
 
-        if in_redacted {
-            current_block.push(line.to_string());
-            if is_tlp_boundary(trimmed) {
-                blocks.push(current_block.join("\n"));
-                current_block.clear();
-                in_redacted = false;
+/// Opening line of a private callout: `> [!private]`, optionally foldable.
+fn private_callout_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(r"(?i)^\s*>\s*\[!private\][+-]?").expect("callout pattern must compile")
+    })
+}
+
+/// Opening tag of a private details element: `<details class="private">`.
+fn private_details_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(r#"(?i)^\s*<details\b[^>]*\bclass\s*=\s*["'][^"']*\bprivate\b"#)
+            .expect("details pattern must compile")
+    })
+}
+
+/// A block in one of the alternative syntaxes `.tlp` enabled, starting at
+/// `line`: the lines it covers, consumed from `lines`. Unterminated comments
+/// and details run to the end of the content — fail-safe.
+fn alternative_block<'a>(
+    line: &'a str,
+    lines: &mut Peekable<Lines<'a>>,
+    opts: &Redaction,
+) -> Option<Vec<&'a str>> {
+    // A %% left open on this line runs to the line that closes it
+    if comment_delimiters(line, opts).len() % 2 == 1 {
+        let mut block = vec![line];
+        for next in lines.by_ref() {
+            block.push(next);
+            if comment_delimiters(next, opts).len() % 2 == 1 {
+                break;
             }
         }
+        return Some(block);
     }
 
-    // Unterminated block — still captured (matches redact_tlp_sections behavior)
-    if in_redacted && !current_block.is_empty() {
-        blocks.push(current_block.join("\n"));
+    if opts.enables(Syntax::Callouts) && private_callout_regex().is_match(line) {
+        let mut block = vec![line];
+        while let Some(&next) = lines.peek().filter(|l| l.trim_start().starts_with('>')) {
+            block.push(next);
+            lines.next();
+        }
+        return Some(block);
     }
 
-    blocks
+    if opts.enables(Syntax::Details) && private_details_regex().is_match(line) {
+        let depth = |l: &str| {
+            let lower = l.to_ascii_lowercase();
+            lower.matches("<details").count().cast_signed()
+                - lower.matches("</details>").count().cast_signed()
+        };
+        let mut open = depth(line);
+        let mut block = vec![line];
+        while open > 0 {
+            let Some(next) = lines.next() else { break };
+            open += depth(next);
+            block.push(next);
+        }
+        return Some(block);
+    }
+
+    None
 }
 
-/// Extract each inline `#tlp/red` redacted span from a line, in order.
-/// Returns the raw text that was replaced by `[REDACTED]` inline.
-fn extract_inline_chunks(line: &str) -> Vec<String> {
-    if !line.contains(TLP_RED_MARKER) {
+// ─── Fields ───
+//
+// `.tlp` can list fields whose values are hidden while their keys stay
+// visible: frontmatter keys (`salary: 150k`) and Dataview inline fields
+// (`salary:: 150k`, `[salary:: 150k]`, `(salary:: 150k)`).
+
+/// A Dataview field filling the rest of its line, after any list bullet,
+/// quote marker or bold key.
+fn line_field_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(
+            r"^[\s>]*(?:(?:[-*+]|\d+[.)])\s+)?(?:\*\*|__)?(\w[\w \-/]*?)(?:\*\*|__)?::[ \t]*(.*?)[ \t]*$",
+        )
+        .expect("field pattern must compile")
+    })
+}
+
+/// A Dataview field in square brackets or parentheses, anywhere on a line.
+fn bracketed_field_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(concat!(
+            r"\[(\w[\w \-/]*?)::[ \t]*([^\]]*?)[ \t]*\]",
+            r"|\((\w[\w \-/]*?)::[ \t]*([^)]*?)[ \t]*\)",
+        ))
+        .expect("field pattern must compile")
+    })
+}
+
+/// A frontmatter `key: value` line, optionally a list item.
+fn frontmatter_field_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(r"^\s*(?:-\s+)?([^\s:#'\x22-][^:]*?)\s*:(?:[ \t]+(.*?))?[ \t]*$")
+            .expect("frontmatter field pattern must compile")
+    })
+}
+
+/// Byte ranges of the values of listed Dataview fields on a line, outside
+/// inline code when code is ignored.
+fn inline_field_values(line: &str, opts: &Redaction) -> Vec<Range<usize>> {
+    if opts.fields.is_empty() || !line.contains("::") {
         return Vec::new();
     }
+    let code = if opts.ignores_code() {
+        code_spans(line)
+    } else {
+        Vec::new()
+    };
+    let whole = line_field_regex().captures(line).into_iter();
+    let bracketed = bracketed_field_regex().captures_iter(line);
+    whole
+        .chain(bracketed)
+        .filter_map(|caps| {
+            let key = caps.get(1).or_else(|| caps.get(3))?;
+            let value = caps.get(2).or_else(|| caps.get(4))?;
+            let shown = value.is_empty() || !opts.hides_field(key.as_str());
+            (!shown && !code.iter().any(|span| span.contains(&key.start())))
+                .then_some(value.range())
+        })
+        .collect()
+}
 
-    let mut chunks = Vec::new();
-    let mut remaining = line;
+/// Number of lines in the frontmatter, delimiters included, if the content
+/// opens with a closed `---` block.
+fn frontmatter_lines(content: &str) -> Option<usize> {
+    let mut lines = content.lines();
+    if lines.next()? != "---" {
+        return None;
+    }
+    lines.position(|l| l.trim_end() == "---").map(|i| i + 2)
+}
 
-    while let Some(red_pos) = remaining.find(TLP_RED_MARKER) {
-        let after_marker = &remaining[red_pos + TLP_RED_MARKER.len()..];
+/// Frontmatter split into parts: the value of each listed field is hidden,
+/// inline after its key or, for block and nested values, as the block of
+/// more-indented lines below it.
+fn frontmatter_parts<'a>(header: &[&'a str], opts: &Redaction) -> Vec<Part<'a>> {
+    let mut parts = Vec::new();
+    let mut i = 0;
+    while i < header.len() {
+        let line = header[i];
+        i += 1;
+        let field = frontmatter_field_regex()
+            .captures(line)
+            .filter(|caps| opts.hides_field(&caps[1]));
+        let Some(caps) = field else {
+            parts.push(Part::Line(line));
+            continue;
+        };
+        let value = caps.get(2).filter(|v| !v.is_empty());
+        if let Some(value) = value.filter(|v| !is_block_indicator(v.as_str())) {
+            parts.push(Part::Field(line, value.range()));
+            continue;
+        }
 
-        let mut closest: Option<(usize, usize)> = None;
-        for &tag in ALL_TLP_MARKERS {
-            if tag == TLP_RED_MARKER {
-                continue;
-            }
-            if let Some(pos) = after_marker.find(tag) {
-                match closest {
-                    None => closest = Some((pos, tag.len())),
-                    Some((prev, _)) if pos < prev => closest = Some((pos, tag.len())),
-                    _ => {}
-                }
+        // Block scalars and nested values continue on more-indented lines
+        // (or, for lists, `-` items at the key's own indent)
+        parts.push(Part::Line(line));
+        let indent = indent_width(line);
+        let continues = |l: &str| {
+            let item = l.trim_start().starts_with("- ") || l.trim() == "-";
+            indent_width(l) > indent || (value.is_none() && item)
+        };
+        let mut last = i;
+        for (j, next) in header.iter().enumerate().skip(i) {
+            if continues(next) {
+                last = j + 1;
+            } else if !next.trim().is_empty() {
+                break;
             }
         }
-
-        if let Some((pos, tag_len)) = closest {
-            let end = red_pos + TLP_RED_MARKER.len() + pos + tag_len;
-            chunks.push(remaining[red_pos..end].to_string());
-            remaining = &remaining[end..];
-        } else {
-            chunks.push(remaining[red_pos..].to_string());
-            remaining = "";
+        if last > i {
+            parts.push(Part::Block(header[i..last].to_vec()));
         }
+        i = last;
     }
+    parts
+}
 
-    chunks
+/// True for a YAML block scalar header: `|`, `>`, `|-`, `>+2`…
+fn is_block_indicator(value: &str) -> bool {
+    let mut chars = value.chars();
+    matches!(chars.next(), Some('|' | '>')) && chars.all(|c| matches!(c, '-' | '+' | '0'..='9'))
 }
 
-/// Extract all inline TLP redacted chunks from multi-line content, in order.
-/// Only processes lines that are NOT inside a block-level #tlp/red section
-/// (those are handled by `extract_tlp_blocks`).
-pub fn extract_inline_tlp_chunks(content: &str) -> Vec<String> {
-    let mut chunks = Vec::new();
-    let mut in_block = false;
+/// A line of content as the redaction pass sees it.
+enum Part<'a> {
+    /// Shown, apart from any inline `#tlp/red` spans.
+    Line(&'a str),
+    /// Inside a code fence: shown as-is.
+    Code(&'a str),
+    /// Whole lines hidden behind a single placeholder line.
+    Block(Vec<&'a str>),
+    /// A frontmatter line whose value, at this byte range, is hidden.
+    Field(&'a str, Range<usize>),
+}
+
+/// Split content into shown lines and hidden blocks. Redaction and every
+/// extraction function walk this same sequence, so they always agree on
+/// what was hidden and in which order.
+fn parts<'a>(content: &'a str, opts: &Redaction) -> Vec<Part<'a>> {
+    let mut parts = Vec::new();
+    let mut lines = content.lines().peekable();
+    let mut fence: Option<&str> = None;
 
-    for line in content.lines() {
+    if let Some(n) = frontmatter_lines(content).filter(|_| !opts.fields.is_empty()) {
+        let header: Vec<&str> = lines.by_ref().take(n).collect();
+        parts.extend(frontmatter_parts(&header, opts));
+    }
+
+    while let Some(line) = lines.next() {
         let trimmed = line.trim();
 
-        if trimmed == TLP_RED_MARKER && !in_block {
-            in_block = true;
+        // Code fences, and everything in them, are shown verbatim
+        if opts.ignores_code() {
+            let open = fence;
+            fence = toggle_closed_fence(fence, line, lines.clone());
+            if open.is_some() || fence.is_some() {
+                parts.push(Part::Code(line));
+                continue;
+            }
+        }
+
+        if let Some(block) = alternative_block(line, &mut lines, opts) {
+            parts.push(Part::Block(block));
             continue;
         }
-        if in_block {
-            if is_tlp_boundary(trimmed) {
-                in_block = false;
+
+        // Block mode: a whole-line hidden tag up to a whole-line tag the audience may see
+        if opens_hidden(trimmed, opts) {
+            let mut block = vec![line];
+            let mut inner: Option<&str> = None;
+            for next in lines.by_ref() {
+                block.push(next);
+                if opts.ignores_code() {
+                    inner = toggle_fence(inner, next);
+                }
+                if inner.is_none() && closes_hidden(next.trim(), opts) {
+                    break;
+                }
             }
+            parts.push(Part::Block(block));
             continue;
         }
 
-        chunks.extend(extract_inline_chunks(line));
+        // Heading mode: the tagged heading stays a line, its section is hidden
+        if let Some(level) = heading_level(line).filter(|_| has_hidden(line, opts)) {
+            parts.push(Part::Line(line));
+            let mut body = Vec::new();
+            let mut inner: Option<&str> = None;
+            while let Some(&next) = lines.peek() {
+                if inner.is_none() && heading_level(next).is_some_and(|l| l <= level) {
+                    break;
+                }
+                inner = toggle_fence(inner, next);
+                body.push(next);
+                lines.next();
+            }
+            // Blank lines around the section stay visible
+            let first = body.iter().position(|l| !l.trim().is_empty());
+            let last = body.iter().rposition(|l| !l.trim().is_empty());
+            let (Some(first), Some(last)) = (first, last) else {
+                parts.extend(body.into_iter().map(Part::Line));
+                continue;
+            };
+            parts.extend(body[..first].iter().map(|&l| Part::Line(l)));
+            parts.push(Part::Block(body[first..=last].to_vec()));
+            parts.extend(body[last + 1..].iter().map(|&l| Part::Line(l)));
+            continue;
+        }
+
+        // Outline mode: the tagged list item stays a line, its children are hidden
+        if let Some(indent) = list_item_indent(line).filter(|_| has_hidden(line, opts)) {
+            parts.push(Part::Line(line));
+            let mut children = Vec::new();
+            let mut blanks = Vec::new();
+            while let Some(&next) = lines.peek() {
+                if next.trim().is_empty() {
+                    blanks.push(next);
+                } else if indent_width(next) > indent {
+                    children.append(&mut blanks);
+                    children.push(next);
+                } else {
+                    break;
+                }
+                lines.next();
+            }
+            // Blank lines after the subtree belong to what follows it
+            if !children.is_empty() {
+                parts.push(Part::Block(children));
+            }
+            parts.extend(blanks.into_iter().map(Part::Line));
+            continue;
+        }
+
+        parts.push(Part::Line(line));
     }
+    parts
+}
 
-    chunks
+/// Indentation of a list item (`-`, `*`, `+`, `1.` or `1)` followed by a
+/// space), if the line is one.
+fn list_item_indent(line: &str) -> Option<usize> {
+    let trimmed = line.trim_start();
+    let bullet = if trimmed.starts_with(['-', '*', '+']) {
+        1
+    } else {
+        let digits = trimmed.chars().take_while(char::is_ascii_digit).count();
+        let closed = digits > 0 && trimmed[digits..].starts_with(['.', ')']);
+        if !closed {
+            return None;
+        }
+        digits + 1
+    };
+    let rest = &trimmed[bullet..];
+    (rest.is_empty() || rest.starts_with([' ', '\t'])).then(|| indent_width(line))
 }
 
-/// Extract each secret pattern match from content, in document order.
-/// Run this on TLP-redacted content (same pipeline stage as `safe-read`).
-pub fn extract_secret_matches(content: &str) -> Vec<String> {
-    let re = secret_regex();
-    let mut matches = Vec::new();
+/// Width of a line's leading whitespace, counting a tab as four columns.
+fn indent_width(line: &str) -> usize {
+    line.chars()
+        .take_while(|c| c.is_whitespace())
+        .map(|c| if c == '\t' { 4 } else { 1 })
+        .sum()
+}
 
-    for line in content.lines() {
-        for m in re.find_iter(line) {
-            matches.push(m.as_str().to_string());
-        }
+/// Level of an ATX heading (`#` to `######` followed by a space), if the
+/// line is one. `#tlp/red` alone is a tag, not a heading.
+fn heading_level(line: &str) -> Option<usize> {
+    let trimmed = line.trim_start();
+    if line.len() - trimmed.len() > 3 {
+        return None;
     }
+    let level = trimmed.chars().take_while(|&c| c == '#').count();
+    let rest = &trimmed[level..];
+    ((1..=6).contains(&level) && (rest.is_empty() || rest.starts_with([' ', '\t'])))
+        .then_some(level)
+}
+
+/// Track fenced code blocks: returns the open fence after this line.
+/// Headings inside a fence don't end a heading section.
+fn toggle_fence<'a>(open: Option<&'a str>, line: &'a str) -> Option<&'a str> {
+    let trimmed = line.trim_start();
+    let fence = ["```", "~~~"].into_iter().find(|f| trimmed.starts_with(f));
+    match (open, fence) {
+        (None, Some(f)) => Some(f),
+        (Some(open), Some(f)) if f == open => None,
+        (open, _) => open,
+    }
+}
+
+/// `toggle_fence` for fences that would switch redaction off: one only opens
+/// if a closing fence follows in `rest`. An unclosed fence is literal text,
+/// so it can't leave the rest of the file unredacted.
+fn toggle_closed_fence<'a>(
+    open: Option<&'a str>,
+    line: &'a str,
+    mut rest: impl Iterator<Item = &'a str>,
+) -> Option<&'a str> {
+    match toggle_fence(open, line) {
+        Some(f) if open.is_none() && !rest.any(|l| l.trim_start().starts_with(f)) => None,
+        fence => fence,
+    }
+}
+
+/// Strip content between #tlp/red and any other #tlp/* boundary marker.
+/// Each RED section is replaced with a single [REDACTED] line.
+///
+/// Supports four modes:
+/// - **Block mode**: `#tlp/red` alone on a line starts a multi-line redacted section,
+///   ended by any `#tlp/*` boundary tag alone on a line.
+/// - **Heading mode**: a heading carrying `#tlp/red` stays visible (the tag itself
+///   redacted inline); everything below it up to the next heading of the same or
+///   a higher level is redacted.
+/// - **Outline mode**: a list item carrying `#tlp/red` has its text redacted
+///   inline and its whole nested subtree (the more-indented lines below) redacted.
+/// - **Inline mode**: `#tlp/red` mid-line redacts from the marker to the next
+///   `#tlp/*` boundary tag on the same line, or to end of line if none found.
+///
+/// Uses the default `Redaction` settings, an AMBER audience: only RED sections
+/// are hidden. See `redact_tlp_sections_for` for other audiences.
+pub fn redact_tlp_sections(content: &str) -> String {
+    redact_tlp_sections_with(content, &Redaction::default())
+}
+
+/// `redact_tlp_sections` for a target audience: every section marked above
+/// `audience` is hidden by the same four modes. A CLEAR view strips RED, AMBER
+/// and GREEN sections; a GREEN view strips RED and AMBER. A hidden section
+/// runs until a tag the audience may see, so anything nested in it stays hidden.
+pub fn redact_tlp_sections_for(content: &str, audience: Tlp) -> String {
+    let opts = Redaction {
+        audience,
+        ..Redaction::default()
+    };
+    redact_tlp_sections_with(content, &opts)
+}
+
+/// `redact_tlp_sections` under a vault's `redaction` settings.
+pub fn redact_tlp_sections_with(content: &str, opts: &Redaction) -> String {
+    redact_tlp(content, opts, &mut || REDACTED_MARKER.to_string())
+}
+
+/// Like `redact_tlp_sections`, but each placeholder carries its position in
+/// document order — `[REDACTED#1]`, `[REDACTED#2]`… — matching the index + 1
+/// of the span in `extract_tlp_spans`.
+pub fn redact_tlp_numbered(content: &str) -> String {
+    redact_tlp_numbered_with(content, &Redaction::default())
+}
+
+pub fn redact_tlp_numbered_with(content: &str, opts: &Redaction) -> String {
+    let mut n = 0;
+    redact_tlp(content, opts, &mut || {
+        n += 1;
+        format!("[REDACTED#{n}]")
+    })
+}
+
+/// Shared by both redaction flavours; `mark` yields each placeholder in turn.
+fn redact_tlp(content: &str, opts: &Redaction, mark: &mut dyn FnMut() -> String) -> String {
+    let result: Vec<String> = parts(content, opts)
+        .into_iter()
+        .map(|part| match part {
+            Part::Block(_) => mark(),
+            Part::Code(line) => line.to_string(),
+            Part::Line(line) => redact_inline_markers(line, opts, mark),
+            Part::Field(line, value) => {
+                format!("{}{}{}", &line[..value.start], mark(), &line[value.end..])
+            }
+        })
+        .collect();
+
+    let mut output = result.join("\n");
+    if content.ends_with('\n') {
+        output.push('\n');
+    }
+    output
+}
+
+/// Process a single line for inline `#tlp/red` markers.
+/// Redacts from each `#tlp/red` to the next `#tlp/*` boundary tag, or to end of line.
+fn redact_inline_markers(line: &str, opts: &Redaction, mark: &mut dyn FnMut() -> String) -> String {
+    let mut result = String::new();
+    let mut last = 0;
+    for span in inline_spans(line, opts) {
+        result.push_str(&line[last..span.start]);
+        result.push_str(&mark());
+        last = span.end;
+    }
+    result.push_str(&line[last..]);
+    result
+}
+
+// ─── Extraction (inverse of redaction, used by safe-write) ───
+
+/// Redaction marker that replaces #tlp/red blocks in safe-read output.
+pub const REDACTED_MARKER: &str = "[REDACTED]";
+
+/// Redaction marker that replaces secret patterns in safe-read output.
+pub const SECRET_MARKER: &str = "[SECRET REDACTED]";
+
+/// Redaction marker that replaces detected personal data in safe-read output.
+pub const PII_MARKER: &str = "[PII REDACTED]";
+
+/// Extract the original content of each #tlp/red block and heading section,
+/// in document order. Each entry holds exactly the lines that were replaced by
+/// a single `[REDACTED]` line in `redact_tlp_sections`.
+pub fn extract_tlp_blocks(content: &str) -> Vec<String> {
+    extract_tlp_blocks_with(content, &Redaction::default())
+}
+
+pub fn extract_tlp_blocks_with(content: &str, opts: &Redaction) -> Vec<String> {
+    parts(content, opts)
+        .into_iter()
+        .filter_map(|part| match part {
+            Part::Block(lines) => Some(lines.join("\n")),
+            Part::Line(_) | Part::Code(_) | Part::Field(..) => None,
+        })
+        .collect()
+}
+
+/// Extract each inline `#tlp/red` redacted span from a line, in order.
+/// Returns the raw text that was replaced by `[REDACTED]` inline.
+fn extract_inline_chunks(line: &str, opts: &Redaction) -> Vec<String> {
+    inline_spans(line, opts)
+        .into_iter()
+        .map(|span| line[span].to_string())
+        .collect()
+}
+
+/// Extract all inline TLP redacted chunks from multi-line content, in order.
+/// Only processes lines that are NOT inside a block-level #tlp/red section
+/// (those are handled by `extract_tlp_blocks`).
+pub fn extract_inline_tlp_chunks(content: &str) -> Vec<String> {
+    extract_inline_tlp_chunks_with(content, &Redaction::default())
+}
+
+pub fn extract_inline_tlp_chunks_with(content: &str, opts: &Redaction) -> Vec<String> {
+    parts(content, opts)
+        .into_iter()
+        .flat_map(|part| match part {
+            Part::Line(line) => extract_inline_chunks(line, opts),
+            Part::Field(line, value) => vec![line[value].to_string()],
+            Part::Block(_) | Part::Code(_) => Vec::new(),
+        })
+        .collect()
+}
+
+/// Extract every #tlp/red block and inline span, in document order — the
+/// originals behind `[REDACTED#1]`, `[REDACTED#2]`… in `redact_tlp_numbered`.
+pub fn extract_tlp_spans(content: &str) -> Vec<String> {
+    extract_tlp_spans_with(content, &Redaction::default())
+}
+
+pub fn extract_tlp_spans_with(content: &str, opts: &Redaction) -> Vec<String> {
+    parts(content, opts)
+        .into_iter()
+        .flat_map(|part| match part {
+            Part::Block(lines) => vec![lines.join("\n")],
+            Part::Line(line) => extract_inline_chunks(line, opts),
+            Part::Field(line, value) => vec![line[value].to_string()],
+            Part::Code(_) => Vec::new(),
+        })
+        .collect()
+}
 
-    matches
+/// Extract each secret from content, in document order — single-line
+/// matches and whole multi-line spans alike.
+/// Run this on TLP-redacted content (same pipeline stage as `safe-read`).
+pub fn extract_secret_matches(content: &str) -> Vec<String> {
+    secret_spans(content)
+        .into_iter()
+        .map(|span| content[span].to_string())
+        .collect()
 }
 
 /// Restore hidden content into new text by replacing markers with originals.
@@ -414,27 +1174,289 @@ pub fn restore_hidden(
     Ok(output)
 }
 
-/// Scan content for known secret patterns and redact them.
+/// Numbered placeholders: `[REDACTED#n]`, `[SECRET REDACTED#n]` and `[PII REDACTED#n]`.
+fn numbered_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(r"\[(SECRET |PII )?REDACTED#(\d+)\]").expect("placeholder pattern must compile")
+    })
+}
+
+/// True if the text contains any placeholder, numbered or not.
+pub fn has_placeholders(text: &str) -> bool {
+    text.contains(REDACTED_MARKER)
+        || text.contains(SECRET_MARKER)
+        || text.contains(PII_MARKER)
+        || has_numbered(text)
+}
+
+/// True if the text contains a numbered placeholder.
+pub fn has_numbered(text: &str) -> bool {
+    numbered_regex().is_match(text)
+}
+
+/// Restore hidden content by placeholder ID rather than by order.
+///
+/// `[REDACTED#n]` becomes `tlp_spans[n - 1]`, `[SECRET REDACTED#n]`
+/// becomes `secrets[n - 1]` and `[PII REDACTED#n]` becomes `pii[n - 1]`,
+/// wherever they now appear, so sections may be moved or swapped. Every ID
+/// must appear exactly once: an unknown, repeated or missing ID — or an
+/// unnumbered placeholder alongside numbered ones — is an error.
+pub fn restore_numbered(
+    new_content: &str,
+    tlp_spans: &[String],
+    secrets: &[String],
+    pii: &[String],
+) -> Result<String, String> {
+    if [REDACTED_MARKER, SECRET_MARKER, PII_MARKER]
+        .iter()
+        .any(|marker| new_content.contains(marker))
+    {
+        return Err(
+            "Content mixes numbered and unnumbered placeholders — keep the ones safe-read \
+             emitted"
+                .to_string(),
+        );
+    }
+
+    // One entry per placeholder kind, by its label in the placeholder
+    let labels = ["", "SECRET ", "PII "];
+    let originals = [tlp_spans, secrets, pii];
+    let mut used = originals.map(|o| vec![false; o.len()]);
+    let mut error = None;
+
+    let restored = numbered_regex().replace_all(new_content, |caps: &regex::Captures| {
+        let label = caps.get(1).map_or("", |m| m.as_str());
+        let kind = labels.iter().position(|&l| l == label).unwrap_or(0);
+        let (originals, used) = (originals[kind], &mut used[kind]);
+        let id = caps[2].parse::<usize>().unwrap_or(0);
+        match id.checked_sub(1).filter(|&i| i < originals.len()) {
+            Some(i) if used[i] => {
+                error.get_or_insert_with(|| format!("{} appears more than once", &caps[0]));
+                String::new()
+            }
+            Some(i) => {
+                used[i] = true;
+                originals[i].clone()
+            }
+            None => {
+                error.get_or_insert_with(|| {
+                    format!("{} does not match any hidden content", &caps[0])
+                });
+                String::new()
+            }
+        }
+    });
+    if let Some(e) = error {
+        return Err(e);
+    }
+
+    let missing: Vec<String> = labels
+        .iter()
+        .zip(&used)
+        .flat_map(|(label, used)| {
+            used.iter()
+                .enumerate()
+                .filter(|(_, &u)| !u)
+                .map(move |(i, _)| format!("[{label}REDACTED#{}]", i + 1))
+        })
+        .collect();
+    if !missing.is_empty() {
+        return Err(format!(
+            "Missing {} — the content would drop hidden material from the original file",
+            missing.join(", ")
+        ));
+    }
+
+    Ok(restored.into_owned())
+}
+
+/// Put an edited `safe_view_with` text back over the original: every
+/// placeholder gets the content it stood for, under the same ID rules as
+/// `restore_numbered`.
+pub fn restore_view(original: &str, view: &str, opts: &Redaction) -> Result<String, String> {
+    check_no_literal_placeholders(original)?;
+    let tlp_redacted = redact_tlp_numbered_with(original, opts);
+    let secrets = extract_secret_matches(&tlp_redacted);
+    let (secret_redacted, _) = redact_secrets_numbered(&tlp_redacted);
+    restore_numbered(
+        view,
+        &extract_tlp_spans_with(original, opts),
+        &secrets,
+        &extract_pii_matches(&secret_redacted, opts),
+    )
+}
+
+/// Refuse to restore into content that already holds placeholder text of
+/// its own: in the view it can't be told apart from the placeholders
+/// redaction adds, so restoring could put hidden content in its place.
+pub fn check_no_literal_placeholders(original: &str) -> Result<(), String> {
+    if has_placeholders(original) {
+        return Err(
+            "The file already contains text that looks like a redaction placeholder \
+             ([REDACTED#n], [SECRET REDACTED#n] or [PII REDACTED#n]), so its hidden content \
+             can't be restored safely. Ask the user to edit it."
+                .to_string(),
+        );
+    }
+    Ok(())
+}
+
+/// True if `range` of `text` touches part of a numbered placeholder.
+pub fn overlaps_placeholder(text: &str, range: Range<usize>) -> bool {
+    numbered_regex()
+        .find_iter(text)
+        .any(|m| m.start() < range.end && range.start < m.end())
+}
+
+/// Scan content for known secret patterns and redact them. A multi-line
+/// secret becomes a single placeholder.
 /// Returns `(redacted_content, secrets_found)`.
 pub fn redact_secrets(content: &str) -> (String, bool) {
-    let re = secret_regex();
-    let mut found = false;
-    let mut lines = Vec::new();
-
-    for line in content.lines() {
-        if re.is_match(line) {
-            found = true;
-            lines.push(re.replace_all(line, "[SECRET REDACTED]").into_owned());
-        } else {
-            lines.push(line.to_string());
-        }
+    let (output, count) = replace_secrets(content, &mut || SECRET_MARKER.to_string());
+    (output, count > 0)
+}
+
+/// Like `redact_secrets`, but numbered: `[SECRET REDACTED#1]`… in the
+/// order of `extract_secret_matches`. Returns the count.
+pub fn redact_secrets_numbered(content: &str) -> (String, usize) {
+    let mut n = 0;
+    replace_secrets(content, &mut || {
+        n += 1;
+        format!("[SECRET REDACTED#{n}]")
+    })
+}
+
+/// Replace detected personal data with `[PII REDACTED#1]`… in the order of
+/// `extract_pii_matches`. Returns the count.
+pub fn redact_pii_numbered(content: &str, opts: &Redaction) -> (String, usize) {
+    let spans = pii_spans(content, opts);
+    let mut n = 0;
+    let output = replace_spans(content, &spans, &mut || {
+        n += 1;
+        format!("[PII REDACTED#{n}]")
+    });
+    (output, spans.len())
+}
+
+/// Extract each detected piece of personal data, in document order.
+/// Run this on secret-redacted content (same pipeline stage as `safe-read`).
+pub fn extract_pii_matches(content: &str, opts: &Redaction) -> Vec<String> {
+    pii_spans(content, opts)
+        .into_iter()
+        .map(|span| content[span].to_string())
+        .collect()
+}
+
+/// Output of the full `safe-read` pipeline, with tallies of what was hidden.
+pub struct SafeView {
+    pub text: String,
+    /// `#tlp/red` blocks and inline spans replaced by `[REDACTED#n]`.
+    pub redactions: usize,
+    /// Secret pattern matches replaced by `[SECRET REDACTED#n]`.
+    pub secrets: usize,
+    /// Personal data replaced by `[PII REDACTED#n]`.
+    pub pii: usize,
+    /// Hidden `#tlp/red/<compartment>` sections, per compartment.
+    pub compartments: BTreeMap<String, usize>,
+}
+
+/// The full `safe-read` pipeline: TLP sections first, then secrets on what
+/// remains, then any personal data `.tlp` asked to detect. Placeholders are
+/// numbered so `safe-write` can restore by ID.
+pub fn safe_view(content: &str) -> SafeView {
+    safe_view_with(content, &Redaction::default())
+}
+
+/// `safe_view` under a vault's `redaction` settings.
+pub fn safe_view_with(content: &str, opts: &Redaction) -> SafeView {
+    let tlp_redacted = redact_tlp_numbered_with(content, opts);
+    let (secret_redacted, secrets) = redact_secrets_numbered(&tlp_redacted);
+    let (text, pii) = redact_pii_numbered(&secret_redacted, opts);
+    SafeView {
+        text,
+        redactions: extract_tlp_spans_with(content, opts).len(),
+        secrets,
+        pii,
+        compartments: compartment_counts(content, opts),
     }
+}
 
-    let mut output = lines.join("\n");
-    if content.ends_with('\n') {
-        output.push('\n');
+/// Cheap check for anything `safe_view` would hide: a `#tlp/red` marker, a
+/// secret pattern match or, when enabled, personal data. Native tools are only safe on files where this is false.
+pub fn has_hidden_content(content: &str) -> bool {
+    has_hidden_content_with(content, &Redaction::default())
+}
+
+pub fn has_hidden_content_with(content: &str, opts: &Redaction) -> bool {
+    let configured = !opts.syntaxes.is_empty() || !opts.fields.is_empty();
+    let tlp = (content.contains("#tlp/") || configured)
+        && parts(content, opts).iter().any(|part| match part {
+            Part::Block(_) | Part::Field(..) => true,
+            Part::Line(line) => !inline_spans(line, opts).is_empty(),
+            Part::Code(_) => false,
+        });
+    tlp || secret_regex().is_match(content)
+        || key_block_regex().is_match(content)
+        || !yaml_block_secrets(content).is_empty()
+        || !pii_spans(content, opts).is_empty()
+}
+
+/// Number of `#tlp/red` markers in the content, compartments and code included.
+pub fn count_red_markers(content: &str) -> usize {
+    tag_positions(content, TLP_RED_MARKER).count() + compartment_tags(content).count()
+}
+
+/// Number of spans hidden in `old` that `new` no longer hides: no hidden span
+/// of `new` contains them. Checked again with every compartment named in
+/// `new` revealed, since those may be revealed later — moving a section into
+/// a new compartment exposes it too.
+pub fn exposed_spans(old: &str, new: &str, opts: &Redaction) -> usize {
+    let mut revealing = opts.clone();
+    revealing
+        .reveal
+        .extend(compartment_tags(new).map(|(_, name)| name.to_string()));
+    [opts, &revealing]
+        .into_iter()
+        .map(|o| {
+            let hidden = extract_tlp_spans_with(new, o);
+            extract_tlp_spans_with(old, o)
+                .iter()
+                .filter(|span| !hidden.iter().any(|h| still_hides(h, span)))
+                .count()
+        })
+        .max()
+        .unwrap_or(0)
+}
+
+/// True if every line of `span` appears, in order, within the lines of `hidden`
+/// — text added inside a hidden section keeps the original hidden.
+fn still_hides(hidden: &str, span: &str) -> bool {
+    let mut lines = hidden.lines();
+    span.lines()
+        .all(|wanted| lines.by_ref().any(|line| line.contains(wanted)))
+}
+
+/// Hidden `#tlp/red/<compartment>` sections in the content, per compartment.
+pub fn compartment_counts(content: &str, opts: &Redaction) -> BTreeMap<String, usize> {
+    let mut counts = BTreeMap::new();
+    let mut fence: Option<&str> = None;
+    let mut lines = content.lines();
+    while let Some(line) = lines.next() {
+        if opts.ignores_code() {
+            let open = fence;
+            fence = toggle_closed_fence(fence, line, lines.clone());
+            if open.is_some() || fence.is_some() {
+                continue;
+            }
+        }
+        for marker in line_markers(line, opts).into_iter().filter(|m| m.hidden) {
+            if let Some(name) = marker.compartment {
+                *counts.entry(name.to_string()).or_default() += 1;
+            }
+        }
     }
-    (output, found)
+    counts
 }
 
 #[cfg(test)]
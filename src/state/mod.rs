@@ -0,0 +1,51 @@
+use std::env;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Environment variable that overrides the state directory in test builds
+/// (the `test-state-dir` feature). Release builds ignore it: the agent sets the
+/// environment of every CLI it runs, and could bring its own key and grants.
+pub const STATE_DIR_ENV: &str = "TLP_STATE_DIR";
+
+/// Environment variable carrying the Claude Code session id into CLI tools.
+/// Exported by `tlp session-start` via `CLAUDE_ENV_FILE`.
+pub const SESSION_ENV: &str = "TLP_SESSION_ID";
+
+/// User-level directory for TLP state (audit log, session records).
+///
+/// Lives outside every vault so the agent's file tools never point at it.
+/// Resolution order: `$TLP_STATE_DIR` (test builds only),
+/// `$XDG_STATE_HOME/forge-tlp`, `$HOME/.local/state/forge-tlp`. Returns None
+/// if none can be determined.
+pub fn state_dir() -> Option<PathBuf> {
+    if cfg!(any(test, feature = "test-state-dir")) {
+        if let Some(dir) = env::var_os(STATE_DIR_ENV).filter(|v| !v.is_empty()) {
+            return Some(PathBuf::from(dir));
+        }
+    }
+    if let Some(dir) = env::var_os("XDG_STATE_HOME").filter(|v| !v.is_empty()) {
+        return Some(PathBuf::from(dir).join("forge-tlp"));
+    }
+    let home = env::var_os("HOME").filter(|v| !v.is_empty())?;
+    Some(PathBuf::from(home).join(".local/state/forge-tlp"))
+}
+
+/// Session id of the calling CLI tool, if the session exported one.
+pub fn session_id() -> Option<String> {
+    env::var(SESSION_ENV).ok().filter(|v| !v.is_empty())
+}
+
+/// True if a session id is safe to embed in file names and shell scripts.
+pub fn is_valid_session_id(id: &str) -> bool {
+    !id.is_empty()
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// Current time in Unix seconds.
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
@@ -0,0 +1,106 @@
+use crate::config::Redaction;
+use crate::frontmatter;
+use crate::redact;
+use crate::tlp::{self, Tlp};
+use std::path::Path;
+
+/// Frontmatter key that classifies a note.
+pub const LEVEL_KEY: &str = "tlp";
+
+/// True if the path names a `.tlp` config, wherever it is.
+pub fn is_config(path: &str) -> bool {
+    Path::new(path)
+        .file_name()
+        .is_some_and(|name| name == ".tlp")
+}
+
+/// Classification declared in a note's frontmatter, if it is a valid level.
+fn declared_level(content: &str) -> Option<Tlp> {
+    frontmatter::get_value(content, LEVEL_KEY).and_then(|v| tlp::from_str(&v))
+}
+
+/// How a change from `old` to `new` weakens the note's own classification,
+/// or None if it doesn't: the `tlp` frontmatter level is removed or lowered,
+/// `#tlp/red` markers are removed, or hidden content is no longer hidden.
+///
+/// Uses the default `Redaction` settings; see `weakening_with`.
+pub fn weakening(old: &str, new: &str) -> Option<String> {
+    weakening_with(old, new, &Redaction::default())
+}
+
+/// `weakening` under a vault's `redaction` settings.
+pub fn weakening_with(old: &str, new: &str, opts: &Redaction) -> Option<String> {
+    if let Some(before) = declared_level(old) {
+        match declared_level(new) {
+            None => {
+                return Some(format!(
+                    "removes the `{LEVEL_KEY}: {}` frontmatter",
+                    before.as_str()
+                ))
+            }
+            Some(after) if tlp::most_restrictive(before, after) != after => {
+                return Some(format!(
+                    "lowers the `{LEVEL_KEY}` frontmatter from {} to {}",
+                    before.as_str(),
+                    after.as_str()
+                ))
+            }
+            Some(_) => {}
+        }
+    }
+
+    let (before, after) = (
+        redact::count_red_markers(old),
+        redact::count_red_markers(new),
+    );
+    if after < before {
+        return Some(format!("removes {} #tlp/red marker(s)", before - after));
+    }
+
+    // A marker can survive while its section escapes: a boundary tag
+    // inserted after it, or a move into a compartment that can be revealed
+    let exposed = redact::exposed_spans(old, new, opts);
+    (exposed > 0).then(|| format!("exposes {exposed} hidden section(s)"))
+}
+
+/// Shell commands that can change a file named in their arguments.
+const WRITING_COMMANDS: &[&str] = &[
+    "rm", "unlink", "mv", "cp", "ln", "install", "tee", "truncate", "touch", "dd", "sed", "perl",
+    "chmod", "rsync",
+];
+
+/// True if a shell command looks like it creates, changes or deletes a
+/// `.tlp` file: a writing command with a `.tlp` argument, or a redirection
+/// into one.
+///
+/// Best effort, like `policy::delete_targets`: reading a `.tlp` with `cat`
+/// or `grep` passes, anything that names one as output does not.
+pub fn shell_touches_config(command: &str) -> bool {
+    command.split(['\n', ';', '|', '&']).any(|part| {
+        let words: Vec<&str> = part.split_whitespace().collect();
+        let names_config = |word: &str| is_config(word.trim_matches(['"', '\'']));
+
+        // `> .tlp`, `>> .tlp`, `>.tlp`, `2>.tlp`
+        let redirected = words.iter().enumerate().any(|(i, word)| {
+            let target = word.trim_start_matches(|c: char| c.is_ascii_digit());
+            match target.trim_start_matches('>') {
+                "" if target.starts_with('>') => words.get(i + 1).is_some_and(|w| names_config(w)),
+                rest if target.starts_with('>') => names_config(rest),
+                _ => false,
+            }
+        });
+
+        let program = words
+            .iter()
+            .copied()
+            .find(|w| !w.contains('=') && *w != "sudo" && *w != "git")
+            .unwrap_or_default();
+        let writing =
+            WRITING_COMMANDS.contains(&program) && words.iter().skip(1).any(|w| names_config(w));
+
+        redirected || writing
+    })
+}
+
+#[cfg(test)]
+mod tests;
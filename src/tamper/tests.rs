@@ -0,0 +1,85 @@
+use super::*;
+
+#[test]
+fn test_is_config() {
+    assert!(is_config(".tlp"));
+    assert!(is_config("/vault/Contacts/.tlp"));
+    assert!(!is_config("/vault/notes.tlp"));
+    assert!(!is_config("/vault/.tlp.bak"));
+}
+
+#[test]
+fn test_lowering_frontmatter() {
+    let red = "---\ntlp: RED\n---\nbody\n";
+    assert!(weakening(red, "---\ntlp: GREEN\n---\nbody\n")
+        .unwrap()
+        .contains("from RED to GREEN"));
+    assert!(weakening(red, "body\n").unwrap().contains("removes"));
+    assert!(weakening(red, "---\ntitle: x\n---\nbody\n").is_some());
+}
+
+#[test]
+fn test_raising_or_keeping_frontmatter() {
+    let green = "---\ntlp: GREEN\n---\nbody\n";
+    assert!(weakening(green, "---\ntlp: AMBER\n---\nbody\n").is_none());
+    assert!(weakening(green, "---\ntlp: green\n---\nchanged body\n").is_none());
+    assert!(weakening("no frontmatter\n", "---\ntlp: RED\n---\n").is_none());
+}
+
+#[test]
+fn test_removing_red_marker() {
+    let old = "Public\n\n#tlp/red\nSalary\n#tlp/amber\n\nPIN #tlp/red 1234\n";
+    assert!(weakening(old, "Public\n\nSalary\n\nPIN #tlp/red 1234\n")
+        .unwrap()
+        .contains("1 #tlp/red marker"));
+    assert!(weakening(old, &old.replace("Public", "Open")).is_none());
+    assert!(weakening(old, &format!("{old}#tlp/red\nmore\n")).is_none());
+}
+
+#[test]
+fn test_shell_touches_config() {
+    assert!(shell_touches_config("rm .tlp"));
+    assert!(shell_touches_config("echo 'GREEN:' > /vault/.tlp"));
+    assert!(shell_touches_config("echo x >>.tlp"));
+    assert!(shell_touches_config(
+        "cd /vault && sed -i 's/RED/GREEN/' .tlp"
+    ));
+    assert!(shell_touches_config("cat new | tee Contacts/.tlp"));
+    assert!(shell_touches_config("git mv .tlp old.tlp"));
+    assert!(shell_touches_config("cp /tmp/open \"/vault/.tlp\""));
+
+    assert!(!shell_touches_config("cat .tlp"));
+    assert!(!shell_touches_config("grep RED /vault/.tlp > /tmp/out"));
+    assert!(!shell_touches_config("rm notes.md"));
+}
+
+#[test]
+fn test_exposing_hidden_sections() {
+    let old = "Public\n#tlp/red\nsalary is 150k\n#tlp/clear\n";
+
+    // The marker survives, but a boundary tag now ends the block before the text
+    let closed = old.replace("#tlp/red\n", "#tlp/red\n#tlp/amber\n");
+    assert!(weakening(old, &closed)
+        .unwrap()
+        .contains("exposes 1 hidden section"));
+
+    // Moving a section into a compartment that could be revealed
+    let moved = old.replace("#tlp/red\n", "#tlp/red/x\n");
+    assert!(weakening(old, &moved).unwrap().contains("exposes"));
+
+    // Renaming a hidden field
+    let opts = Redaction {
+        fields: vec!["salary".into()],
+        ..Redaction::default()
+    };
+    let field = "salary:: 150k\n";
+    assert!(weakening_with(field, "pay:: 150k\n", &opts).is_some());
+    assert!(weakening_with(field, "salary:: 150k\nmore\n", &opts).is_none());
+
+    // Growing or moving a hidden section is fine
+    let grown = old.replace("150k\n", "150k\nbonus 10k\n");
+    assert!(weakening(old, &grown).is_none());
+    assert!(weakening(old, &format!("Intro\n{old}")).is_none());
+    let kept = "#tlp/red/health\ncheckup\n#tlp/clear\n";
+    assert!(weakening(kept, &format!("{kept}more\n")).is_none());
+}
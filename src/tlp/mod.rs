@@ -1,9 +1,11 @@
+use crate::config;
 use crate::frontmatter;
+use crate::pin;
 use crate::vault;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
-#[derive(Debug, PartialEq, Clone, Copy)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
 pub enum Tlp {
     Red,
     Amber,
@@ -11,59 +13,131 @@ pub enum Tlp {
     Clear,
 }
 
+impl Tlp {
+    /// Upper-case level name, as written in `.tlp` headers and frontmatter.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Tlp::Red => "RED",
+            Tlp::Amber => "AMBER",
+            Tlp::Green => "GREEN",
+            Tlp::Clear => "CLEAR",
+        }
+    }
+
+    /// Whether an audience at this level may see a section marked `section`:
+    /// RED sees everything, CLEAR sees only CLEAR.
+    pub fn sees(self, section: Tlp) -> bool {
+        section.rank() <= self.rank()
+    }
+
+    fn rank(self) -> u8 {
+        match self {
+            Tlp::Clear => 0,
+            Tlp::Green => 1,
+            Tlp::Amber => 2,
+            Tlp::Red => 3,
+        }
+    }
+}
+
 /// Result of classifying a file's TLP level.
 pub struct Classification {
     pub level: Tlp,
     pub rel_path: String,
+    pub vault_root: PathBuf,
+    /// What decided the level: the matching `.tlp` pattern, `frontmatter`, or `default`.
+    pub rule: String,
     pub config_error: bool,
+    /// Why `config_error` was set, for the refusal message.
+    pub diagnostic: Option<String>,
 }
 
 /// Classify a file's TLP level using vault config + frontmatter override.
 /// Returns None if no vault is found (file outside any vault).
-/// If .tlp exists but can't be read, returns RED with `config_error` = true.
+/// If .tlp exists but can't be read or parsed, or a pinned `.tlp` changed
+/// since `tlp pin`, returns RED with `config_error` = true.
 pub fn classify_file(file_path: &str) -> Option<Classification> {
-    let vault_root = vault::find_vault(file_path)?;
+    let found = vault::find_vault(file_path);
+
+    // A pinned tree fails closed even if its .tlp was deleted outright
+    if let Err(mismatch) = pin::verify(file_path, found.as_deref()) {
+        let vault_root = found.unwrap_or(mismatch.root);
+        let rel_path = Path::new(file_path)
+            .strip_prefix(&vault_root)
+            .unwrap_or(Path::new(file_path));
+        return Some(Classification {
+            level: Tlp::Red,
+            rel_path: rel_path.to_string_lossy().to_string(),
+            vault_root,
+            rule: "pin".to_string(),
+            config_error: true,
+            diagnostic: Some(format!("Pinned .tlp mismatch: {}", mismatch.problem)),
+        });
+    }
+
+    let vault_root = found?;
     let abs_path = Path::new(file_path);
     let rel_path = abs_path.strip_prefix(&vault_root).ok()?;
     let rel_str = rel_path.to_string_lossy().to_string();
 
-    let config_path = vault_root.join(".tlp");
-    let Ok(config) = fs::read_to_string(&config_path) else {
+    let Some(config) = config::read(&vault_root) else {
         return Some(Classification {
             level: Tlp::Red,
             rel_path: rel_str,
+            vault_root,
+            rule: ".tlp unreadable".to_string(),
             config_error: true,
+            diagnostic: Some("Malformed .tlp config".to_string()),
         });
     };
 
-    let path_level = classify(rel_path, &config);
+    if let Err(problem) = config::try_load(&vault_root) {
+        return Some(Classification {
+            level: Tlp::Red,
+            rel_path: rel_str,
+            vault_root,
+            rule: ".tlp malformed".to_string(),
+            config_error: true,
+            diagnostic: Some(problem),
+        });
+    }
+
+    let (path_level, path_rule) = match match_rule(rel_path, &config) {
+        Some((level, pattern)) => (level, pattern),
+        None => (Tlp::Amber, "default".to_string()),
+    };
 
     // Check frontmatter override: take the more restrictive of path and frontmatter
-    let level = match fs::read_to_string(abs_path) {
-        Ok(content) => {
-            if let Some(val) = frontmatter::get_value(&content, "tlp") {
-                if let Some(fm_level) = from_str(&val) {
-                    most_restrictive(path_level, fm_level)
-                } else {
-                    path_level
-                }
-            } else {
-                path_level
-            }
+    let fm_level = fs::read_to_string(abs_path)
+        .ok()
+        .and_then(|content| frontmatter::get_value(&content, "tlp"))
+        .and_then(|val| from_str(&val));
+    let (level, rule) = match fm_level {
+        Some(fm) if most_restrictive(path_level, fm) != path_level => {
+            (fm, "frontmatter".to_string())
         }
-        Err(_) => path_level,
+        _ => (path_level, path_rule),
     };
 
     Some(Classification {
         level,
         rel_path: rel_str,
+        vault_root,
+        rule,
         config_error: false,
+        diagnostic: None,
     })
 }
 
 /// Parse .tlp config and classify a relative path. First match wins.
 /// Returns Amber for files not matched by any pattern.
 pub fn classify(rel_path: &Path, config: &str) -> Tlp {
+    match_rule(rel_path, config).map_or(Tlp::Amber, |(level, _)| level)
+}
+
+/// Find the first `.tlp` pattern matching a relative path.
+/// Returns the level and the pattern, or None if nothing matches.
+pub fn match_rule(rel_path: &Path, config: &str) -> Option<(Tlp, String)> {
     let rel_str = rel_path.to_string_lossy();
     let mut current_level: Option<Tlp> = None;
 
@@ -74,35 +148,29 @@ pub fn classify(rel_path: &Path, config: &str) -> Tlp {
             continue;
         }
 
-        if let Some(level) = parse_level_header(trimmed) {
-            current_level = Some(level);
+        // A top-level key opens a section: a level list, or a setting whose
+        // nested keys and quoted list items must not be read as patterns
+        if !line.starts_with(char::is_whitespace) && !trimmed.starts_with('-') {
+            current_level = parse_level_header(trimmed);
             continue;
         }
 
         if let Some(pattern) = parse_pattern_line(trimmed) {
             if let Some(level) = current_level {
                 if matches_pattern(&rel_str, pattern) {
-                    return level;
+                    return Some((level, pattern.to_string()));
                 }
             }
         }
     }
 
-    Tlp::Amber
+    None
 }
 
 /// Return the more restrictive of two TLP levels.
 /// RED > AMBER > GREEN > CLEAR.
 pub fn most_restrictive(a: Tlp, b: Tlp) -> Tlp {
-    let rank = |t: Tlp| -> u8 {
-        match t {
-            Tlp::Red => 3,
-            Tlp::Amber => 2,
-            Tlp::Green => 1,
-            Tlp::Clear => 0,
-        }
-    };
-    if rank(a) >= rank(b) {
+    if a.rank() >= b.rank() {
         a
     } else {
         b
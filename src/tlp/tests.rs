@@ -131,3 +131,36 @@ fn test_double_star_as_green_catchall() {
     );
     assert_eq!(classify(Path::new("anything.md"), config), Tlp::Green);
 }
+
+#[test]
+fn test_settings_sections_are_not_patterns() {
+    let config = "\
+GREEN:
+  - \"Topics/**\"
+
+secrets:
+  allow:
+    - \"samples/**\"
+
+policy:
+  RED:
+    create: deny
+
+AMBER:
+  - \"Journals/**\"
+";
+    assert_eq!(classify(Path::new("samples/key.md"), config), Tlp::Amber);
+    assert_eq!(match_rule(Path::new("samples/key.md"), config), None);
+    assert_eq!(classify(Path::new("Topics/rust.md"), config), Tlp::Green);
+    assert_eq!(classify(Path::new("Journals/a.md"), config), Tlp::Amber);
+}
+
+#[test]
+fn test_audience_sees_its_level_and_below() {
+    assert!(Tlp::Red.sees(Tlp::Red));
+    assert!(Tlp::Amber.sees(Tlp::Green));
+    assert!(!Tlp::Amber.sees(Tlp::Red));
+    assert!(Tlp::Green.sees(Tlp::Clear));
+    assert!(!Tlp::Green.sees(Tlp::Amber));
+    assert!(!Tlp::Clear.sees(Tlp::Green));
+}
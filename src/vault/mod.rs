@@ -1,7 +1,11 @@
+use crate::cache;
 use std::path::{Path, PathBuf};
 
 /// Walk up from a starting directory looking for .tlp.
-fn find_vault_from_dir(start: &Path) -> Option<PathBuf> {
+pub fn find_vault_from_dir(start: &Path) -> Option<PathBuf> {
+    if let Some(root) = cache::with(|c| c.vault_root(start)) {
+        return root;
+    }
     let mut dir = start;
     loop {
         if dir.join(".tlp").exists() {
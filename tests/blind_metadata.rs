@@ -5,12 +5,27 @@ use predicates::prelude::*;
 use std::fs;
 use tempfile::tempdir;
 
+// ─── Helpers ───
+
+thread_local! {
+    /// The test harness runs each test on its own thread, so every test gets a
+    /// state dir of its own, removed when the test ends.
+    static STATE_DIR: tempfile::TempDir = tempdir().expect("create state dir");
+}
+
+/// Spawn a binary with an isolated `TLP_STATE_DIR`, so tests never touch the
+/// real audit log, pins, approvals, key or daemon socket.
+fn bin(name: &str) -> Command {
+    let mut cmd = Command::cargo_bin(name).expect("binary is built");
+    STATE_DIR.with(|dir| cmd.env("TLP_STATE_DIR", dir.path()));
+    cmd
+}
+
 // ─── Basic usage ───
 
 #[test]
 fn no_args_exits_1_with_usage() {
-    Command::cargo_bin("blind-metadata")
-        .unwrap()
+    bin("blind-metadata")
         .assert()
         .code(1)
         .stderr(predicate::str::contains("Usage"));
@@ -19,8 +34,7 @@ fn no_args_exits_1_with_usage() {
 #[test]
 fn unknown_action_exits_1() {
     let dir = tempdir().unwrap();
-    Command::cargo_bin("blind-metadata")
-        .unwrap()
+    bin("blind-metadata")
         .args(["delete", dir.path().to_str().unwrap(), "key"])
         .assert()
         .code(1)
@@ -35,8 +49,7 @@ fn set_creates_frontmatter() {
     let file = dir.path().join("note.md");
     fs::write(&file, "Hello body").unwrap();
 
-    Command::cargo_bin("blind-metadata")
-        .unwrap()
+    bin("blind-metadata")
         .args(["set", dir.path().to_str().unwrap(), "tlp", "RED"])
         .assert()
         .success()
@@ -53,8 +66,7 @@ fn set_updates_existing_key() {
     let file = dir.path().join("note.md");
     fs::write(&file, "---\ntlp: GREEN\n---\nBody").unwrap();
 
-    Command::cargo_bin("blind-metadata")
-        .unwrap()
+    bin("blind-metadata")
         .args(["set", dir.path().to_str().unwrap(), "tlp", "RED"])
         .assert()
         .success();
@@ -70,8 +82,7 @@ fn set_adds_new_key() {
     let file = dir.path().join("note.md");
     fs::write(&file, "---\ntitle: Hello\n---\nBody").unwrap();
 
-    Command::cargo_bin("blind-metadata")
-        .unwrap()
+    bin("blind-metadata")
         .args(["set", dir.path().to_str().unwrap(), "tlp", "AMBER"])
         .assert()
         .success();
@@ -81,13 +92,27 @@ fn set_adds_new_key() {
     assert!(content.contains("title: Hello"));
 }
 
+#[test]
+fn set_refuses_to_lower_tlp() {
+    let dir = tempdir().unwrap();
+    let file = dir.path().join("note.md");
+    fs::write(&file, "---\ntlp: RED\n---\nHello body").unwrap();
+
+    bin("blind-metadata")
+        .args(["set", dir.path().to_str().unwrap(), "tlp", "GREEN"])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("refused: note.md"));
+
+    assert!(fs::read_to_string(&file).unwrap().contains("tlp: RED"));
+}
+
 #[test]
 fn set_without_value_exits_1() {
     let dir = tempdir().unwrap();
     fs::write(dir.path().join("note.md"), "body").unwrap();
 
-    Command::cargo_bin("blind-metadata")
-        .unwrap()
+    bin("blind-metadata")
         .args(["set", dir.path().to_str().unwrap(), "tlp"])
         .assert()
         .code(1)
@@ -101,8 +126,7 @@ fn get_shows_values() {
     let dir = tempdir().unwrap();
     fs::write(dir.path().join("note.md"), "---\ntlp: RED\n---\nBody").unwrap();
 
-    Command::cargo_bin("blind-metadata")
-        .unwrap()
+    bin("blind-metadata")
         .args(["get", dir.path().to_str().unwrap(), "tlp"])
         .assert()
         .success()
@@ -114,8 +138,7 @@ fn get_missing_key_shows_zero() {
     let dir = tempdir().unwrap();
     fs::write(dir.path().join("note.md"), "---\ntitle: Hi\n---\nBody").unwrap();
 
-    Command::cargo_bin("blind-metadata")
-        .unwrap()
+    bin("blind-metadata")
         .args(["get", dir.path().to_str().unwrap(), "tlp"])
         .assert()
         .success()
@@ -130,8 +153,7 @@ fn has_lists_missing_files() {
     fs::write(dir.path().join("a.md"), "---\ntitle: A\n---\n").unwrap();
     fs::write(dir.path().join("b.md"), "---\ntlp: RED\n---\n").unwrap();
 
-    Command::cargo_bin("blind-metadata")
-        .unwrap()
+    bin("blind-metadata")
         .args(["has", dir.path().to_str().unwrap(), "tlp"])
         .assert()
         .success()
@@ -147,8 +169,7 @@ fn ignores_non_md_files() {
     fs::write(dir.path().join("note.md"), "---\ntlp: RED\n---\n").unwrap();
     fs::write(dir.path().join("data.json"), r#"{"key":"value"}"#).unwrap();
 
-    Command::cargo_bin("blind-metadata")
-        .unwrap()
+    bin("blind-metadata")
         .args(["get", dir.path().to_str().unwrap(), "tlp"])
         .assert()
         .success()
@@ -159,8 +180,7 @@ fn ignores_non_md_files() {
 
 #[test]
 fn nonexistent_dir_exits_1() {
-    Command::cargo_bin("blind-metadata")
-        .unwrap()
+    bin("blind-metadata")
         .args(["get", "/tmp/nonexistent-dir-12345", "tlp"])
         .assert()
         .code(1)
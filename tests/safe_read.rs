@@ -1,8 +1,11 @@
 #![allow(deprecated)] // Command::cargo_bin is the standard assert_cmd API
 
 use assert_cmd::Command;
+use context_tlp::approval::{self, Grant};
+use context_tlp::state::now;
 use predicates::prelude::*;
 use std::fs;
+use std::path::Path;
 use tempfile::tempdir;
 
 // ─── Fixture constants ───
@@ -27,13 +30,29 @@ const CONTENT_INLINE_UNTERMINATED: &str = include_str!("fixtures/content/inline_
 const CONTENT_WITH_STRIPE_KEY: &str = include_str!("fixtures/content/with_stripe_key.md");
 const CONTENT_WITH_AWS_KEY: &str = include_str!("fixtures/content/with_aws_key.md");
 const CONTENT_MULTIPLE_SECRETS: &str = include_str!("fixtures/content/multiple_secrets.md");
+const CONTENT_WITH_PRIVATE_KEY: &str = include_str!("fixtures/content/with_private_key.md");
+
+// ─── Helpers ───
+
+thread_local! {
+    /// The test harness runs each test on its own thread, so every test gets a
+    /// state dir of its own, removed when the test ends.
+    static STATE_DIR: tempfile::TempDir = tempdir().expect("create state dir");
+}
+
+/// Spawn a binary with an isolated `TLP_STATE_DIR`, so tests never touch the
+/// real audit log, pins, approvals, key or daemon socket.
+fn bin(name: &str) -> Command {
+    let mut cmd = Command::cargo_bin(name).expect("binary is built");
+    STATE_DIR.with(|dir| cmd.env("TLP_STATE_DIR", dir.path()));
+    cmd
+}
 
 // ─── Basic usage ───
 
 #[test]
 fn no_args_exits_1_with_usage() {
-    Command::cargo_bin("safe-read")
-        .unwrap()
+    bin("safe-read")
         .assert()
         .code(1)
         .stderr(predicate::str::contains("Usage"));
@@ -41,8 +60,7 @@ fn no_args_exits_1_with_usage() {
 
 #[test]
 fn nonexistent_file_exits_1() {
-    Command::cargo_bin("safe-read")
-        .unwrap()
+    bin("safe-read")
         .arg("/tmp/this-file-does-not-exist-12345.md")
         .assert()
         .code(1)
@@ -57,8 +75,7 @@ fn plain_file_outputs_content() {
     let file = dir.path().join("test.md");
     fs::write(&file, CONTENT_PLAIN).unwrap();
 
-    Command::cargo_bin("safe-read")
-        .unwrap()
+    bin("safe-read")
         .arg(file.to_str().unwrap())
         .assert()
         .success()
@@ -73,12 +90,11 @@ fn redacts_tlp_red_section() {
     let file = dir.path().join("test.md");
     fs::write(&file, CONTENT_WITH_REDACTION).unwrap();
 
-    Command::cargo_bin("safe-read")
-        .unwrap()
+    bin("safe-read")
         .arg(file.to_str().unwrap())
         .assert()
         .success()
-        .stdout(predicate::str::contains("[REDACTED]"))
+        .stdout(predicate::str::contains("[REDACTED#1]"))
         .stdout(predicate::str::contains("Secret").not());
 }
 
@@ -88,14 +104,14 @@ fn redacts_multiple_sections() {
     let file = dir.path().join("test.md");
     fs::write(&file, CONTENT_MULTIPLE_REDACTIONS).unwrap();
 
-    let output = Command::cargo_bin("safe-read")
-        .unwrap()
+    let output = bin("safe-read")
         .arg(file.to_str().unwrap())
         .output()
         .unwrap();
 
     let stdout = String::from_utf8_lossy(&output.stdout);
-    assert_eq!(stdout.matches("[REDACTED]").count(), 2);
+    assert!(stdout.contains("[REDACTED#1]") && stdout.contains("[REDACTED#2]"));
+    assert!(!stdout.contains("[REDACTED#3]"));
     assert!(stdout.contains("A\n"));
     assert!(stdout.contains("B\n"));
     assert!(stdout.contains("C\n"));
@@ -107,12 +123,11 @@ fn redacts_unterminated_block() {
     let file = dir.path().join("test.md");
     fs::write(&file, CONTENT_UNTERMINATED_RED).unwrap();
 
-    Command::cargo_bin("safe-read")
-        .unwrap()
+    bin("safe-read")
         .arg(file.to_str().unwrap())
         .assert()
         .success()
-        .stdout(predicate::str::contains("[REDACTED]"))
+        .stdout(predicate::str::contains("[REDACTED#1]"))
         .stdout(predicate::str::contains("Secret").not());
 }
 
@@ -124,12 +139,11 @@ fn redacts_api_keys() {
     let file = dir.path().join("test.md");
     fs::write(&file, CONTENT_WITH_API_KEY).unwrap();
 
-    Command::cargo_bin("safe-read")
-        .unwrap()
+    bin("safe-read")
         .arg(file.to_str().unwrap())
         .assert()
         .success()
-        .stdout(predicate::str::contains("[SECRET REDACTED]"))
+        .stdout(predicate::str::contains("[SECRET REDACTED#1]"))
         .stdout(predicate::str::contains("sk-ant-api03").not())
         .stderr(predicate::str::contains("WARNING"));
 }
@@ -140,12 +154,11 @@ fn redacts_gitlab_token() {
     let file = dir.path().join("test.md");
     fs::write(&file, CONTENT_WITH_GITLAB_TOKEN).unwrap();
 
-    Command::cargo_bin("safe-read")
-        .unwrap()
+    bin("safe-read")
         .arg(file.to_str().unwrap())
         .assert()
         .success()
-        .stdout(predicate::str::contains("[SECRET REDACTED]"))
+        .stdout(predicate::str::contains("[SECRET REDACTED#1]"))
         .stdout(predicate::str::contains("glpat-").not());
 }
 
@@ -155,8 +168,7 @@ fn no_false_positive_on_short_sk() {
     let file = dir.path().join("test.md");
     fs::write(&file, CONTENT_SHORT_SK).unwrap();
 
-    Command::cargo_bin("safe-read")
-        .unwrap()
+    bin("safe-read")
         .arg(file.to_str().unwrap())
         .assert()
         .success()
@@ -171,13 +183,12 @@ fn full_pipeline_redacts_both() {
     let file = dir.path().join("test.md");
     fs::write(&file, CONTENT_FULL_PIPELINE).unwrap();
 
-    Command::cargo_bin("safe-read")
-        .unwrap()
+    bin("safe-read")
         .arg(file.to_str().unwrap())
         .assert()
         .success()
-        .stdout(predicate::str::contains("[REDACTED]"))
-        .stdout(predicate::str::contains("[SECRET REDACTED]"))
+        .stdout(predicate::str::contains("[REDACTED#1]"))
+        .stdout(predicate::str::contains("[SECRET REDACTED#1]"))
         .stdout(predicate::str::contains("top secret").not())
         .stdout(predicate::str::contains("sk-ant-api03").not());
 }
@@ -191,8 +202,7 @@ fn refuses_red_file_by_path() {
     let file = dir.path().join("secret.pdf");
     fs::write(&file, "binary content").unwrap();
 
-    Command::cargo_bin("safe-read")
-        .unwrap()
+    bin("safe-read")
         .arg(file.to_str().unwrap())
         .assert()
         .code(1)
@@ -206,27 +216,295 @@ fn refuses_red_file_by_frontmatter() {
     let file = dir.path().join("secret.md");
     fs::write(&file, CONTENT_FRONTMATTER_RED).unwrap();
 
-    Command::cargo_bin("safe-read")
-        .unwrap()
+    bin("safe-read")
         .arg(file.to_str().unwrap())
         .assert()
         .code(1)
         .stderr(predicate::str::contains("TLP:RED"));
 }
 
+// ─── Markers in code ───
+
+const CONTENT_MARKER_DOCS: &str = "\
+To hide a line, tag it `#tlp/red`:
+
+```markdown
+Salary #tlp/red 120k
+```
+
+Tags like #tlp/reddit are left alone. Real: #tlp/red my pin is 1234
+";
+
+#[test]
+fn markers_in_code_are_shown_as_written() {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join(".tlp"), CONFIG_GREEN_MD).unwrap();
+    let file = dir.path().join("docs.md");
+    fs::write(&file, CONTENT_MARKER_DOCS).unwrap();
+
+    bin("safe-read")
+        .arg(file.to_str().unwrap())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("tag it `#tlp/red`:"))
+        .stdout(predicate::str::contains("Salary #tlp/red 120k"))
+        .stdout(predicate::str::contains("#tlp/reddit are left alone"))
+        .stdout(predicate::str::contains("Real: [REDACTED#1]\n"));
+}
+
+#[test]
+fn markers_in_code_redact_when_configured() {
+    let dir = tempdir().unwrap();
+    let config = format!("{CONFIG_GREEN_MD}\nredaction:\n  code: redact\n");
+    fs::write(dir.path().join(".tlp"), config).unwrap();
+    let file = dir.path().join("docs.md");
+    fs::write(&file, CONTENT_MARKER_DOCS).unwrap();
+
+    bin("safe-read")
+        .arg(file.to_str().unwrap())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("120k").not())
+        .stdout(predicate::str::contains("[REDACTED#3]"));
+}
+
+const CONTENT_COMPARTMENTS: &str = "\
+Plan #tlp/red/health checkup.
+Budget #tlp/red/finance 20k.
+Loan #tlp/red/finance 5k.
+";
+
 #[test]
-fn allows_amber_file() {
+fn reports_compartment_counts() {
+    let dir = tempdir().unwrap();
+    let file = dir.path().join("notes.md");
+    fs::write(&file, CONTENT_COMPARTMENTS).unwrap();
+
+    bin("safe-read")
+        .arg(file.to_str().unwrap())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("checkup").not())
+        .stderr(predicate::str::contains("finance: 2, health: 1"));
+}
+
+#[test]
+fn grant_reveals_compartment() {
+    let state = tempdir().unwrap();
+    let dir = tempdir().unwrap();
+    let file = dir.path().join("notes.md");
+    fs::write(&file, CONTENT_COMPARTMENTS).unwrap();
+    let mut grant = approve(state.path(), file.to_str().unwrap(), now() + 600);
+    grant.reveal = vec!["health".into()];
+    grant.sign(&approval::load_key(&state.path().join("approval.key")).unwrap());
+
+    bin("safe-read")
+        .env("TLP_STATE_DIR", state.path())
+        .args(["--token", &grant.token(), file.to_str().unwrap()])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("checkup"))
+        .stdout(predicate::str::contains("20k").not())
+        .stderr(predicate::str::contains("finance: 2"))
+        .stderr(predicate::str::contains("health").not());
+
+    // A grant for another file reveals nothing here
+    let other = dir.path().join("other.md");
+    let mut elsewhere = approve(state.path(), other.to_str().unwrap(), now() + 600);
+    elsewhere.reveal = vec!["health".into()];
+    elsewhere.sign(&approval::load_key(&state.path().join("approval.key")).unwrap());
+    bin("safe-read")
+        .env("TLP_STATE_DIR", state.path())
+        .args(["--token", &elsewhere.token(), file.to_str().unwrap()])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("checkup").not());
+}
+
+#[test]
+fn reveal_flag_is_not_accepted() {
+    let dir = tempdir().unwrap();
+    let file = dir.path().join("notes.md");
+    fs::write(&file, CONTENT_COMPARTMENTS).unwrap();
+
+    bin("safe-read")
+        .args(["--reveal", "health", file.to_str().unwrap()])
+        .assert()
+        .code(1)
+        .stdout(predicate::str::contains("checkup").not());
+}
+
+#[test]
+fn tlp_config_reveals_compartment() {
+    let dir = tempdir().unwrap();
+    let config = format!("{CONFIG_GREEN_MD}\nredaction:\n  reveal: [finance]\n");
+    fs::write(dir.path().join(".tlp"), config).unwrap();
+    let file = dir.path().join("notes.md");
+    fs::write(&file, CONTENT_COMPARTMENTS).unwrap();
+
+    bin("safe-read")
+        .arg(file.to_str().unwrap())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("20k"))
+        .stdout(predicate::str::contains("checkup").not());
+}
+
+/// Sign a grant with the state directory's key, as `tlp approve` does.
+fn approve(state: &Path, pattern: &str, expires: u64) -> Grant {
+    let key = approval::load_or_create_key(&state.join("approval.key")).unwrap();
+    let mut grant = Grant {
+        pattern: pattern.to_string(),
+        session_id: None,
+        expires: Some(expires),
+        granted: 0,
+        reveal: Vec::new(),
+        mac: String::new(),
+    };
+    grant.sign(&key);
+    grant
+}
+
+fn amber_vault() -> (tempfile::TempDir, String) {
     let dir = tempdir().unwrap();
     fs::write(dir.path().join(".tlp"), CONFIG_AMBER_MD).unwrap();
     let file = dir.path().join("journal.md");
     fs::write(&file, "diary entry\n").unwrap();
+    let file = file.to_string_lossy().to_string();
+    (dir, file)
+}
 
-    Command::cargo_bin("safe-read")
-        .unwrap()
-        .arg(file.to_str().unwrap())
+#[test]
+fn allows_amber_file_with_token() {
+    let state = tempdir().unwrap();
+    let (_dir, file) = amber_vault();
+    let token = approve(state.path(), &file, now() + 600).token();
+
+    bin("safe-read")
+        .env("TLP_STATE_DIR", state.path())
+        .args(["--token", &token, &file])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("diary entry"));
+
+    bin("safe-read")
+        .env("TLP_STATE_DIR", state.path())
+        .env("TLP_APPROVAL_TOKEN", &token)
+        .arg(&file)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("diary entry"));
+}
+
+#[test]
+fn allows_amber_file_with_recorded_approval() {
+    let state = tempdir().unwrap();
+    let (_dir, file) = amber_vault();
+    let grant = approve(state.path(), &file, now() + 600);
+    approval::save(&state.path().join("approvals.json"), &[grant], now()).unwrap();
+
+    bin("safe-read")
+        .env("TLP_STATE_DIR", state.path())
+        .arg(&file)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("diary entry"));
+}
+
+#[test]
+fn refuses_amber_file_without_approval() {
+    let state = tempdir().unwrap();
+    let (_dir, file) = amber_vault();
+
+    bin("safe-read")
+        .env("TLP_STATE_DIR", state.path())
+        .arg(&file)
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains("tlp approve"))
+        .stdout(predicate::str::contains("diary entry").not());
+}
+
+#[test]
+fn monitor_mode_reads_unapproved_amber_file() {
+    let state = tempdir().unwrap();
+    let (dir, file) = amber_vault();
+    fs::write(
+        dir.path().join(".tlp"),
+        format!("mode: monitor\n\n{CONFIG_AMBER_MD}"),
+    )
+    .unwrap();
+
+    bin("safe-read")
+        .env("TLP_STATE_DIR", state.path())
+        .arg(&file)
         .assert()
         .success()
+        .stderr(predicate::str::contains("would deny"))
         .stdout(predicate::str::contains("diary entry"));
+
+    let log = fs::read_to_string(state.path().join("audit.jsonl")).unwrap();
+    assert_eq!(log.lines().count(), 1);
+    assert!(log.contains(r#""decision":"deny""#) && log.contains(r#""monitor":true"#));
+}
+
+#[test]
+fn tlp_mode_env_does_not_relax_safe_read() {
+    let state = tempdir().unwrap();
+    let (_dir, file) = amber_vault();
+
+    bin("safe-read")
+        .env("TLP_STATE_DIR", state.path())
+        .env("TLP_MODE", "monitor")
+        .arg(&file)
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("diary entry").not());
+}
+
+#[test]
+fn monitor_mode_never_reads_red_file() {
+    let state = tempdir().unwrap();
+    let dir = tempdir().unwrap();
+    fs::write(
+        dir.path().join(".tlp"),
+        "mode: monitor\n\nRED:\n  - \"*.md\"\n",
+    )
+    .unwrap();
+    let file = dir.path().join("secret.md");
+    fs::write(&file, "salary is 150k\n").unwrap();
+
+    bin("safe-read")
+        .env("TLP_STATE_DIR", state.path())
+        .env("TLP_MODE", "monitor")
+        .arg(&file)
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("150k").not());
+
+    let log = fs::read_to_string(state.path().join("audit.jsonl")).unwrap();
+    assert!(log.contains(r#""decision":"deny""#) && !log.contains(r#""monitor":true"#));
+}
+
+#[test]
+fn refuses_amber_file_with_invalid_token() {
+    let state = tempdir().unwrap();
+    let (dir, file) = amber_vault();
+    let other = dir.path().join("other.md").to_string_lossy().to_string();
+
+    let expired = approve(state.path(), &file, now() - 1).token();
+    let elsewhere = approve(state.path(), &other, now() + 600).token();
+    let mut forged = approve(state.path(), &other, now() + 600);
+    forged.pattern.clone_from(&file);
+
+    for token in [expired, elsewhere, forged.token(), "garbage".to_string()] {
+        bin("safe-read")
+            .env("TLP_STATE_DIR", state.path())
+            .args(["--token", &token, &file])
+            .assert()
+            .code(1)
+            .stdout(predicate::str::contains("diary entry").not());
+    }
 }
 
 #[test]
@@ -236,8 +514,7 @@ fn file_outside_vault_still_works() {
     let file = dir.path().join("standalone.md");
     fs::write(&file, "no vault here\n").unwrap();
 
-    Command::cargo_bin("safe-read")
-        .unwrap()
+    bin("safe-read")
         .arg(file.to_str().unwrap())
         .assert()
         .success()
@@ -252,12 +529,11 @@ fn redacts_inline_marker() {
     let file = dir.path().join("test.md");
     fs::write(&file, CONTENT_INLINE_REDACTION).unwrap();
 
-    Command::cargo_bin("safe-read")
-        .unwrap()
+    bin("safe-read")
         .arg(file.to_str().unwrap())
         .assert()
         .success()
-        .stdout(predicate::str::contains("[REDACTED]"))
+        .stdout(predicate::str::contains("[REDACTED#1]"))
         .stdout(predicate::str::contains("secret information").not())
         .stdout(predicate::str::contains("that resumes here"));
 }
@@ -268,12 +544,11 @@ fn redacts_inline_unterminated_marker() {
     let file = dir.path().join("test.md");
     fs::write(&file, CONTENT_INLINE_UNTERMINATED).unwrap();
 
-    Command::cargo_bin("safe-read")
-        .unwrap()
+    bin("safe-read")
         .arg(file.to_str().unwrap())
         .assert()
         .success()
-        .stdout(predicate::str::contains("[REDACTED]"))
+        .stdout(predicate::str::contains("[REDACTED#1]"))
         .stdout(predicate::str::contains("secret to end").not())
         .stdout(predicate::str::contains("Back to normal content"));
 }
@@ -286,12 +561,11 @@ fn redacts_stripe_key() {
     let file = dir.path().join("test.md");
     fs::write(&file, CONTENT_WITH_STRIPE_KEY).unwrap();
 
-    Command::cargo_bin("safe-read")
-        .unwrap()
+    bin("safe-read")
         .arg(file.to_str().unwrap())
         .assert()
         .success()
-        .stdout(predicate::str::contains("[SECRET REDACTED]"))
+        .stdout(predicate::str::contains("[SECRET REDACTED#1]"))
         .stdout(predicate::str::contains("rk_prod_").not());
 }
 
@@ -301,12 +575,11 @@ fn redacts_aws_key() {
     let file = dir.path().join("test.md");
     fs::write(&file, CONTENT_WITH_AWS_KEY).unwrap();
 
-    Command::cargo_bin("safe-read")
-        .unwrap()
+    bin("safe-read")
         .arg(file.to_str().unwrap())
         .assert()
         .success()
-        .stdout(predicate::str::contains("[SECRET REDACTED]"))
+        .stdout(predicate::str::contains("[SECRET REDACTED#1]"))
         .stdout(predicate::str::contains("AKIA").not());
 }
 
@@ -316,18 +589,38 @@ fn multiple_secrets_redacted() {
     let file = dir.path().join("test.md");
     fs::write(&file, CONTENT_MULTIPLE_SECRETS).unwrap();
 
-    let output = Command::cargo_bin("safe-read")
-        .unwrap()
+    let output = bin("safe-read")
         .arg(file.to_str().unwrap())
         .output()
         .unwrap();
 
     let stdout = String::from_utf8_lossy(&output.stdout);
-    assert_eq!(stdout.matches("[SECRET REDACTED]").count(), 2);
+    assert!(stdout.contains("[SECRET REDACTED#2]"));
+    assert!(!stdout.contains("[SECRET REDACTED#3]"));
     assert!(!stdout.contains("ghp_"));
     assert!(!stdout.contains("glpat-"));
 }
 
+#[test]
+fn redacts_whole_key_block_and_yaml_secret() {
+    let dir = tempdir().unwrap();
+    let file = dir.path().join("test.md");
+    fs::write(&file, CONTENT_WITH_PRIVATE_KEY).unwrap();
+
+    bin("safe-read")
+        .arg(file.to_str().unwrap())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("```\n[SECRET REDACTED#1]\n```"))
+        .stdout(predicate::str::contains(
+            "  password: |\n    [SECRET REDACTED#2]\n",
+        ))
+        .stdout(predicate::str::contains("b3BlbnNzaC1rZXkt").not())
+        .stdout(predicate::str::contains("END OPENSSH").not())
+        .stdout(predicate::str::contains("horse").not())
+        .stdout(predicate::str::contains("host: db.internal"));
+}
+
 // ─── File corruption tests ───
 
 #[test]
@@ -336,8 +629,7 @@ fn complex_markdown_passes_through_unchanged() {
     let file = dir.path().join("complex.md");
     fs::write(&file, CONTENT_COMPLEX_MARKDOWN).unwrap();
 
-    Command::cargo_bin("safe-read")
-        .unwrap()
+    bin("safe-read")
         .arg(file.to_str().unwrap())
         .assert()
         .success()
@@ -350,8 +642,7 @@ fn source_code_with_hashes_passes_through_unchanged() {
     let file = dir.path().join("code.md");
     fs::write(&file, CONTENT_SOURCE_CODE).unwrap();
 
-    Command::cargo_bin("safe-read")
-        .unwrap()
+    bin("safe-read")
         .arg(file.to_str().unwrap())
         .assert()
         .success()
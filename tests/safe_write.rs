@@ -1,6 +1,7 @@
 #![allow(deprecated)]
 
 use assert_cmd::Command;
+use context_tlp::approval::{self, Grant};
 use predicates::prelude::*;
 use std::fs;
 use tempfile::tempdir;
@@ -73,12 +74,27 @@ tlp: red
 This should be inaccessible.
 ";
 
+// ─── Helpers ───
+
+thread_local! {
+    /// The test harness runs each test on its own thread, so every test gets a
+    /// state dir of its own, removed when the test ends.
+    static STATE_DIR: tempfile::TempDir = tempdir().expect("create state dir");
+}
+
+/// Spawn a binary with an isolated `TLP_STATE_DIR`, so tests never touch the
+/// real audit log, pins, approvals, key or daemon socket.
+fn bin(name: &str) -> Command {
+    let mut cmd = Command::cargo_bin(name).expect("binary is built");
+    STATE_DIR.with(|dir| cmd.env("TLP_STATE_DIR", dir.path()));
+    cmd
+}
+
 // ─── Edit mode: basic ───
 
 #[test]
 fn edit_no_args_exits_1() {
-    Command::cargo_bin("safe-write")
-        .unwrap()
+    bin("safe-write")
         .assert()
         .code(1)
         .stderr(predicate::str::contains("Usage"));
@@ -90,8 +106,7 @@ fn edit_replaces_unique_string() {
     let file = dir.path().join("test.md");
     fs::write(&file, CONTENT_PLAIN).unwrap();
 
-    Command::cargo_bin("safe-write")
-        .unwrap()
+    bin("safe-write")
         .args([
             "edit",
             file.to_str().unwrap(),
@@ -115,8 +130,7 @@ fn edit_fails_on_missing_string() {
     let file = dir.path().join("test.md");
     fs::write(&file, CONTENT_PLAIN).unwrap();
 
-    Command::cargo_bin("safe-write")
-        .unwrap()
+    bin("safe-write")
         .args([
             "edit",
             file.to_str().unwrap(),
@@ -137,8 +151,7 @@ fn edit_fails_on_ambiguous_string() {
     // "content" appears multiple times in CONTENT_PLAIN-like text
     fs::write(&file, "AAA\nBBB\nAAA\n").unwrap();
 
-    Command::cargo_bin("safe-write")
-        .unwrap()
+    bin("safe-write")
         .args([
             "edit",
             file.to_str().unwrap(),
@@ -161,8 +174,7 @@ fn edit_preserves_tlp_block_when_editing_visible_text() {
     fs::write(&file, CONTENT_WITH_TLP_BLOCK).unwrap();
 
     // Edit visible text — hidden block should survive
-    Command::cargo_bin("safe-write")
-        .unwrap()
+    bin("safe-write")
         .args([
             "edit",
             file.to_str().unwrap(),
@@ -183,14 +195,94 @@ fn edit_preserves_tlp_block_when_editing_visible_text() {
     assert!(result.contains("Visible bottom."));
 }
 
+#[test]
+fn edit_diff_shows_placeholders_not_hidden_text() {
+    let dir = tempdir().unwrap();
+    let file = dir.path().join("test.md");
+    fs::write(&file, CONTENT_WITH_TLP_BLOCK).unwrap();
+
+    bin("safe-write")
+        .args([
+            "edit",
+            file.to_str().unwrap(),
+            "--old",
+            "Visible bottom.",
+            "--new",
+            "Changed bottom.",
+        ])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("[REDACTED#1]"))
+        .stderr(predicate::str::contains("secret line").not());
+
+    let expected = CONTENT_WITH_TLP_BLOCK.replace("Visible bottom.", "Changed bottom.");
+    assert_eq!(fs::read_to_string(&file).unwrap(), expected);
+}
+
+#[test]
+fn edit_cannot_match_hidden_text() {
+    let dir = tempdir().unwrap();
+    let file = dir.path().join("test.md");
+    fs::write(&file, CONTENT_WITH_TLP_BLOCK).unwrap();
+
+    // A hit would confirm a guess about the hidden block
+    bin("safe-write")
+        .args([
+            "edit",
+            file.to_str().unwrap(),
+            "--old",
+            "secret line A",
+            "--new",
+            "x",
+        ])
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains("not found"));
+
+    assert_eq!(fs::read_to_string(&file).unwrap(), CONTENT_WITH_TLP_BLOCK);
+}
+
+#[test]
+fn edit_refuses_old_string_overlapping_a_placeholder() {
+    let dir = tempdir().unwrap();
+    let file = dir.path().join("test.md");
+    fs::write(&file, CONTENT_WITH_TLP_BLOCK).unwrap();
+
+    bin("safe-write")
+        .args([
+            "edit",
+            file.to_str().unwrap(),
+            "--old",
+            "ACTED#1]\nVisible",
+            "--new",
+            "x",
+        ])
+        .assert()
+        .code(1);
+
+    bin("safe-write")
+        .args([
+            "edit",
+            file.to_str().unwrap(),
+            "--old",
+            "#1]",
+            "--new",
+            "#2]",
+        ])
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains("overlaps a redaction marker"));
+
+    assert_eq!(fs::read_to_string(&file).unwrap(), CONTENT_WITH_TLP_BLOCK);
+}
+
 #[test]
 fn edit_rejects_redaction_markers_in_old_string() {
     let dir = tempdir().unwrap();
     let file = dir.path().join("test.md");
     fs::write(&file, CONTENT_PLAIN).unwrap();
 
-    Command::cargo_bin("safe-write")
-        .unwrap()
+    bin("safe-write")
         .args([
             "edit",
             file.to_str().unwrap(),
@@ -203,8 +295,7 @@ fn edit_rejects_redaction_markers_in_old_string() {
         .code(1)
         .stderr(predicate::str::contains("redaction markers"));
 
-    Command::cargo_bin("safe-write")
-        .unwrap()
+    bin("safe-write")
         .args([
             "edit",
             file.to_str().unwrap(),
@@ -227,8 +318,7 @@ fn edit_refuses_red_file() {
     let file = dir.path().join("secret.md");
     fs::write(&file, CONTENT_FRONTMATTER_RED).unwrap();
 
-    Command::cargo_bin("safe-write")
-        .unwrap()
+    bin("safe-write")
         .args([
             "edit",
             file.to_str().unwrap(),
@@ -250,15 +340,17 @@ fn write_overwrites_plain_file() {
     let file = dir.path().join("test.md");
     fs::write(&file, CONTENT_PLAIN).unwrap();
 
-    Command::cargo_bin("safe-write")
-        .unwrap()
+    bin("safe-write")
         .args(["write", file.to_str().unwrap()])
-        .write_stdin("New content.\n")
+        .write_stdin("---\ntlp: amber\n---\nNew content.\n")
         .assert()
         .success()
         .stdout(predicate::str::contains(file.to_str().unwrap()));
 
-    assert_eq!(fs::read_to_string(&file).unwrap(), "New content.\n");
+    assert_eq!(
+        fs::read_to_string(&file).unwrap(),
+        "---\ntlp: amber\n---\nNew content.\n"
+    );
 }
 
 #[test]
@@ -266,8 +358,7 @@ fn write_creates_new_file() {
     let dir = tempdir().unwrap();
     let file = dir.path().join("brand_new.md");
 
-    Command::cargo_bin("safe-write")
-        .unwrap()
+    bin("safe-write")
         .args(["write", file.to_str().unwrap()])
         .write_stdin("Fresh content.\n")
         .assert()
@@ -282,8 +373,7 @@ fn write_refuses_empty_stdin() {
     let file = dir.path().join("test.md");
     fs::write(&file, CONTENT_PLAIN).unwrap();
 
-    Command::cargo_bin("safe-write")
-        .unwrap()
+    bin("safe-write")
         .args(["write", file.to_str().unwrap()])
         .write_stdin("")
         .assert()
@@ -311,8 +401,7 @@ Modified top.
 Modified bottom.
 ";
 
-    Command::cargo_bin("safe-write")
-        .unwrap()
+    bin("safe-write")
         .args(["write", file.to_str().unwrap()])
         .write_stdin(new_content)
         .assert()
@@ -347,8 +436,7 @@ API key: [SECRET REDACTED]
 Modified line.
 ";
 
-    Command::cargo_bin("safe-write")
-        .unwrap()
+    bin("safe-write")
         .args(["write", file.to_str().unwrap()])
         .write_stdin(new_content)
         .assert()
@@ -381,8 +469,7 @@ Line with key: [SECRET REDACTED]
 Modified bottom.
 ";
 
-    Command::cargo_bin("safe-write")
-        .unwrap()
+    bin("safe-write")
         .args(["write", file.to_str().unwrap()])
         .write_stdin(new_content)
         .assert()
@@ -416,8 +503,7 @@ Modified top.
 Modified bottom.
 ";
 
-    Command::cargo_bin("safe-write")
-        .unwrap()
+    bin("safe-write")
         .args(["write", file.to_str().unwrap()])
         .write_stdin(new_content)
         .assert()
@@ -438,8 +524,7 @@ fn write_fails_on_extra_redacted_marker() {
     // New content has [REDACTED] but original has no TLP blocks
     let new_content = "Before.\n[REDACTED]\nAfter.\n";
 
-    Command::cargo_bin("safe-write")
-        .unwrap()
+    bin("safe-write")
         .args(["write", file.to_str().unwrap()])
         .write_stdin(new_content)
         .assert()
@@ -464,8 +549,7 @@ tlp: amber
 Modified info [REDACTED] continues here.
 ";
 
-    Command::cargo_bin("safe-write")
-        .unwrap()
+    bin("safe-write")
         .args(["write", file.to_str().unwrap()])
         .write_stdin(new_content)
         .assert()
@@ -488,8 +572,7 @@ fn write_refuses_red_file() {
     let file = dir.path().join("secret.md");
     fs::write(&file, CONTENT_FRONTMATTER_RED).unwrap();
 
-    Command::cargo_bin("safe-write")
-        .unwrap()
+    bin("safe-write")
         .args(["write", file.to_str().unwrap()])
         .write_stdin("overwrite attempt\n")
         .assert()
@@ -497,6 +580,215 @@ fn write_refuses_red_file() {
         .stderr(predicate::str::contains("TLP:RED"));
 }
 
+// ─── Tamper protection ───
+
+#[test]
+fn write_refuses_tlp_config() {
+    let dir = tempdir().unwrap();
+    let config = dir.path().join(".tlp");
+    fs::write(&config, "RED:\n  - \"*.pdf\"\n").unwrap();
+
+    bin("safe-write")
+        .args(["write", config.to_str().unwrap()])
+        .write_stdin("GREEN:\n  - \"**\"\n")
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains(".tlp files are protected"));
+
+    assert_eq!(
+        fs::read_to_string(&config).unwrap(),
+        "RED:\n  - \"*.pdf\"\n"
+    );
+}
+
+#[test]
+fn edit_refuses_lowering_frontmatter() {
+    let dir = tempdir().unwrap();
+    let file = dir.path().join("note.md");
+    fs::write(&file, "---\ntlp: AMBER\n---\nBody\n").unwrap();
+
+    bin("safe-write")
+        .args([
+            "edit",
+            file.to_str().unwrap(),
+            "--old",
+            "tlp: AMBER",
+            "--new",
+            "tlp: CLEAR",
+        ])
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains("from AMBER to CLEAR"));
+
+    assert!(fs::read_to_string(&file).unwrap().contains("tlp: AMBER"));
+}
+
+#[test]
+fn write_refuses_dropping_red_marker() {
+    let dir = tempdir().unwrap();
+    let file = dir.path().join("note.md");
+    fs::write(&file, "Plan\n\nPIN #tlp/red 1234\n").unwrap();
+
+    // The agent rewrites the line without the marker or its placeholder
+    bin("safe-write")
+        .args(["write", file.to_str().unwrap()])
+        .write_stdin("Plan\n\nPIN 1234\n")
+        .assert()
+        .code(1);
+
+    assert!(fs::read_to_string(&file).unwrap().contains("#tlp/red"));
+}
+
+// ─── AMBER approval ───
+
+fn amber_token(state: &std::path::Path, file: &str) -> String {
+    let key = approval::load_or_create_key(&state.join("approval.key")).unwrap();
+    let mut grant = Grant {
+        pattern: file.to_string(),
+        session_id: None,
+        expires: Some(context_tlp::state::now() + 600),
+        granted: 0,
+        reveal: Vec::new(),
+        mac: String::new(),
+    };
+    grant.sign(&key);
+    grant.token()
+}
+
+#[test]
+fn edit_refuses_amber_file_without_approval() {
+    let state = tempdir().unwrap();
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join(".tlp"), "AMBER:\n  - \"*.md\"\n").unwrap();
+    let file = dir.path().join("journal.md");
+    fs::write(&file, "Hello world\n").unwrap();
+
+    bin("safe-write")
+        .env("TLP_STATE_DIR", state.path())
+        .args([
+            "edit",
+            file.to_str().unwrap(),
+            "--old",
+            "Hello",
+            "--new",
+            "Bye",
+        ])
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains("tlp approve"));
+
+    assert_eq!(fs::read_to_string(&file).unwrap(), "Hello world\n");
+}
+
+#[test]
+fn monitor_mode_edits_unapproved_amber_file() {
+    let state = tempdir().unwrap();
+    let dir = tempdir().unwrap();
+    fs::write(
+        dir.path().join(".tlp"),
+        "mode: monitor\n\nAMBER:\n  - \"*.md\"\n",
+    )
+    .unwrap();
+    let file = dir.path().join("journal.md");
+    fs::write(&file, "Hello world\n").unwrap();
+
+    bin("safe-write")
+        .env("TLP_STATE_DIR", state.path())
+        .env_remove("TLP_MODE")
+        .args([
+            "edit",
+            file.to_str().unwrap(),
+            "--old",
+            "Hello",
+            "--new",
+            "Bye",
+        ])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("would deny"));
+
+    assert_eq!(fs::read_to_string(&file).unwrap(), "Bye world\n");
+}
+
+#[test]
+fn edit_amber_file_with_token() {
+    let state = tempdir().unwrap();
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join(".tlp"), "AMBER:\n  - \"*.md\"\n").unwrap();
+    let file = dir.path().join("journal.md");
+    fs::write(&file, "Hello world\n").unwrap();
+    let token = amber_token(state.path(), file.to_str().unwrap());
+
+    bin("safe-write")
+        .env("TLP_STATE_DIR", state.path())
+        .args([
+            "edit",
+            file.to_str().unwrap(),
+            "--old",
+            "Hello",
+            "--new",
+            "Bye",
+        ])
+        .args(["--token", &token, "--quiet"])
+        .assert()
+        .success();
+
+    assert_eq!(fs::read_to_string(&file).unwrap(), "Bye world\n");
+}
+
+#[test]
+fn write_creates_new_amber_file_without_approval() {
+    let state = tempdir().unwrap();
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join(".tlp"), "AMBER:\n  - \"*.md\"\n").unwrap();
+    let file = dir.path().join("new.md");
+
+    bin("safe-write")
+        .env("TLP_STATE_DIR", state.path())
+        .args(["write", file.to_str().unwrap(), "--quiet"])
+        .write_stdin("fresh\n")
+        .assert()
+        .success();
+
+    assert_eq!(fs::read_to_string(&file).unwrap(), "fresh\n");
+}
+
+#[test]
+fn write_refuses_amber_content_copied_into_green_file() {
+    let state = tempdir().unwrap();
+    let dir = tempdir().unwrap();
+    fs::write(
+        dir.path().join(".tlp"),
+        "AMBER:\n  - \"Journals/**\"\n\nGREEN:\n  - \"Topics/**\"\n",
+    )
+    .unwrap();
+    fs::create_dir_all(dir.path().join("Journals")).unwrap();
+    fs::create_dir_all(dir.path().join("Topics")).unwrap();
+    let journal = dir.path().join("Journals/today.md");
+    let entry = "Told Anna the Northwind merger falls through unless the board signs \
+                 before Friday. Nobody else knows yet.\n";
+    fs::write(&journal, entry).unwrap();
+    let token = amber_token(state.path(), journal.to_str().unwrap());
+
+    bin("safe-read")
+        .env("TLP_STATE_DIR", state.path())
+        .env("TLP_SESSION_ID", "s1")
+        .args(["--token", &token, journal.to_str().unwrap()])
+        .assert()
+        .success();
+
+    let topic = dir.path().join("Topics/weekly.md");
+    bin("safe-write")
+        .env("TLP_STATE_DIR", state.path())
+        .env("TLP_SESSION_ID", "s1")
+        .args(["write", topic.to_str().unwrap()])
+        .write_stdin(entry)
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains("AMBER content"));
+    assert!(!topic.exists());
+}
+
 // ─── Write mode: multiple TLP blocks ───
 
 #[test]
@@ -523,8 +815,7 @@ Modified middle.
 [REDACTED]
 Modified bottom.
 ";
-    Command::cargo_bin("safe-write")
-        .unwrap()
+    bin("safe-write")
         .args(["write", file.to_str().unwrap()])
         .write_stdin(new_content)
         .assert()
@@ -539,6 +830,80 @@ Modified bottom.
     assert!(result.contains("Modified bottom."));
 }
 
+// ─── Write mode: numbered placeholders ───
+
+const CONTENT_TWO_BLOCKS: &str = "\
+Top.
+#tlp/red
+Secret block 1.
+#tlp/amber
+Middle.
+#tlp/red
+Secret block 2.
+#tlp/green
+Bottom.
+";
+
+#[test]
+fn write_restores_moved_numbered_blocks() {
+    let dir = tempdir().unwrap();
+    let file = dir.path().join("test.md");
+    fs::write(&file, CONTENT_TWO_BLOCKS).unwrap();
+
+    // The two sections swapped places
+    let new_content = "Top.\n[REDACTED#2]\nMiddle.\n[REDACTED#1]\nBottom.\n";
+    bin("safe-write")
+        .args(["write", file.to_str().unwrap()])
+        .write_stdin(new_content)
+        .assert()
+        .success();
+
+    let result = fs::read_to_string(&file).unwrap();
+    assert_eq!(
+        result,
+        "Top.\n#tlp/red\nSecret block 2.\n#tlp/green\nMiddle.\n\
+         #tlp/red\nSecret block 1.\n#tlp/amber\nBottom.\n"
+    );
+}
+
+#[test]
+fn write_rejects_duplicated_numbered_placeholder() {
+    let dir = tempdir().unwrap();
+    let file = dir.path().join("test.md");
+    fs::write(&file, CONTENT_TWO_BLOCKS).unwrap();
+
+    let new_content = "[REDACTED#1]\n[REDACTED#1]\n[REDACTED#2]\n";
+    bin("safe-write")
+        .args(["write", file.to_str().unwrap()])
+        .write_stdin(new_content)
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains(
+            "[REDACTED#1] appears more than once",
+        ));
+    assert_eq!(fs::read_to_string(&file).unwrap(), CONTENT_TWO_BLOCKS);
+}
+
+#[test]
+fn edit_rejects_numbered_placeholder_in_old_string() {
+    let dir = tempdir().unwrap();
+    let file = dir.path().join("test.md");
+    fs::write(&file, CONTENT_TWO_BLOCKS).unwrap();
+
+    bin("safe-write")
+        .args([
+            "edit",
+            file.to_str().unwrap(),
+            "--old",
+            "Top.\n[REDACTED#1]",
+            "--new",
+            "Top.",
+        ])
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains("redaction markers"));
+}
+
 // ─── Write mode: unterminated TLP block ───
 
 #[test]
@@ -550,8 +915,7 @@ fn write_restores_unterminated_block() {
 
     let new_content = "Modified before.\n[REDACTED]\n";
 
-    Command::cargo_bin("safe-write")
-        .unwrap()
+    bin("safe-write")
         .args(["write", file.to_str().unwrap()])
         .write_stdin(new_content)
         .assert()
@@ -589,8 +953,7 @@ AWS: [SECRET REDACTED]
 Slack: [SECRET REDACTED]
 Modified done.
 ";
-    Command::cargo_bin("safe-write")
-        .unwrap()
+    bin("safe-write")
         .args(["write", file.to_str().unwrap()])
         .write_stdin(new_content)
         .assert()
@@ -603,6 +966,254 @@ Modified done.
     assert!(result.contains("Modified done."));
 }
 
+// ─── Write mode: heading-scoped sections ───
+
+#[test]
+fn write_restores_heading_section() {
+    let dir = tempdir().unwrap();
+    let file = dir.path().join("test.md");
+    let original = "# Review\n\n## Compensation #tlp/red\n\nBase: 120k.\n\n## Next\n\nTodo.\n";
+    fs::write(&file, original).unwrap();
+
+    let view = bin("safe-read")
+        .arg(file.to_str().unwrap())
+        .output()
+        .unwrap();
+    let view = String::from_utf8_lossy(&view.stdout).to_string();
+    assert!(!view.contains("120k"));
+
+    bin("safe-write")
+        .args(["write", file.to_str().unwrap()])
+        .write_stdin(view.replace("Todo.", "Done."))
+        .assert()
+        .success();
+    assert_eq!(
+        fs::read_to_string(&file).unwrap(),
+        original.replace("Todo.", "Done.")
+    );
+}
+
+// ─── Write mode: alternative syntaxes ───
+
+#[test]
+fn write_restores_comments_and_private_callouts() {
+    let dir = tempdir().unwrap();
+    fs::write(
+        dir.path().join(".tlp"),
+        "GREEN:\n  - \"*.md\"\nredaction:\n  syntaxes: [comments, callouts]\n",
+    )
+    .unwrap();
+    let file = dir.path().join("test.md");
+    let original = "Plan %%offer 120k%% soon.\n> [!private]\n> Diagnosis.\nTodo.\n";
+    fs::write(&file, original).unwrap();
+
+    let view = bin("safe-read")
+        .arg(file.to_str().unwrap())
+        .output()
+        .unwrap();
+    let view = String::from_utf8_lossy(&view.stdout).to_string();
+    assert!(!view.contains("120k"));
+    assert!(!view.contains("Diagnosis"));
+
+    bin("safe-write")
+        .args(["write", file.to_str().unwrap()])
+        .write_stdin(view.replace("Todo.", "Done."))
+        .assert()
+        .success();
+    assert_eq!(
+        fs::read_to_string(&file).unwrap(),
+        original.replace("Todo.", "Done.")
+    );
+}
+
+// ─── Write mode: compartments ───
+
+#[test]
+fn write_with_revealed_compartment_restores_the_rest() {
+    let state = tempdir().unwrap();
+    let dir = tempdir().unwrap();
+    let file = dir.path().join("test.md");
+    let original = "Plan #tlp/red/health checkup.\nBudget #tlp/red/finance 20k.\nTodo.\n";
+    fs::write(&file, original).unwrap();
+    let key = approval::load_or_create_key(&state.path().join("approval.key")).unwrap();
+    let mut grant = Grant {
+        pattern: file.to_str().unwrap().to_string(),
+        session_id: None,
+        expires: Some(context_tlp::state::now() + 600),
+        granted: 0,
+        reveal: vec!["health".into()],
+        mac: String::new(),
+    };
+    grant.sign(&key);
+
+    let view = bin("safe-read")
+        .env("TLP_STATE_DIR", state.path())
+        .env("TLP_APPROVAL_TOKEN", grant.token())
+        .arg(file.to_str().unwrap())
+        .output()
+        .unwrap();
+    let view = String::from_utf8_lossy(&view.stdout).to_string();
+    assert!(view.contains("checkup"));
+    assert!(!view.contains("20k"));
+
+    bin("safe-write")
+        .env("TLP_STATE_DIR", state.path())
+        .env("TLP_APPROVAL_TOKEN", grant.token())
+        .args(["write", file.to_str().unwrap()])
+        .write_stdin(view.replace("Todo.", "Done."))
+        .assert()
+        .success();
+    assert_eq!(
+        fs::read_to_string(&file).unwrap(),
+        original.replace("Todo.", "Done.")
+    );
+}
+
+// ─── Write mode: fields ───
+
+#[test]
+fn write_restores_hidden_field_values() {
+    let dir = tempdir().unwrap();
+    fs::write(
+        dir.path().join(".tlp"),
+        "GREEN:\n  - \"*.md\"\nredaction:\n  fields: [phone, salary]\n",
+    )
+    .unwrap();
+    let file = dir.path().join("alice.md");
+    let original = "---\nphone: 555 0100\nrole: engineer\n---\nsalary:: 150k\nTodo.\n";
+    fs::write(&file, original).unwrap();
+
+    let view = bin("safe-read")
+        .arg(file.to_str().unwrap())
+        .output()
+        .unwrap();
+    let view = String::from_utf8_lossy(&view.stdout).to_string();
+    assert!(view.contains("phone: [REDACTED#1]"));
+    assert!(view.contains("salary:: [REDACTED#2]"));
+    assert!(view.contains("role: engineer"));
+
+    bin("safe-write")
+        .args(["write", file.to_str().unwrap()])
+        .write_stdin(view.replace("Todo.", "Done."))
+        .assert()
+        .success();
+    assert_eq!(
+        fs::read_to_string(&file).unwrap(),
+        original.replace("Todo.", "Done.")
+    );
+}
+
+// ─── Write mode: personal data ───
+
+fn pii_vault(original: &str) -> (tempfile::TempDir, std::path::PathBuf) {
+    let dir = tempdir().unwrap();
+    fs::write(
+        dir.path().join(".tlp"),
+        "GREEN:\n  - \"*.md\"\nredaction:\n  pii: [email, phone]\n",
+    )
+    .unwrap();
+    let file = dir.path().join("journal.md");
+    fs::write(&file, original).unwrap();
+    (dir, file)
+}
+
+fn safe_read_view(file: &std::path::Path) -> String {
+    let view = bin("safe-read")
+        .arg(file.to_str().unwrap())
+        .output()
+        .unwrap();
+    String::from_utf8_lossy(&view.stdout).to_string()
+}
+
+#[test]
+fn write_restores_pii() {
+    let original = "Met alice@example.com, +44 20 7946 0958.\nTodo.\n";
+    let (_dir, file) = pii_vault(original);
+
+    let view = safe_read_view(&file);
+    assert_eq!(view, "Met [PII REDACTED#1], [PII REDACTED#2].\nTodo.\n");
+
+    bin("safe-write")
+        .args(["write", file.to_str().unwrap()])
+        .write_stdin(view.replace("Todo.", "Done."))
+        .assert()
+        .success();
+    assert_eq!(
+        fs::read_to_string(&file).unwrap(),
+        original.replace("Todo.", "Done.")
+    );
+}
+
+#[test]
+fn write_refuses_dropping_pii() {
+    let original = "Met alice@example.com.\nTodo.\n";
+    let (_dir, file) = pii_vault(original);
+
+    bin("safe-write")
+        .args(["write", file.to_str().unwrap()])
+        .write_stdin("Met someone.\nDone.\n")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("[PII REDACTED#1]"));
+    assert_eq!(fs::read_to_string(&file).unwrap(), original);
+}
+
+// ─── Write mode: outline subtrees ───
+
+#[test]
+fn write_restores_list_item_subtree() {
+    let dir = tempdir().unwrap();
+    let file = dir.path().join("test.md");
+    let original = "- Salary #tlp/red\n  - Offer: 120k\n    - Bonus\n- Lunch\n";
+    fs::write(&file, original).unwrap();
+
+    let view = bin("safe-read")
+        .arg(file.to_str().unwrap())
+        .output()
+        .unwrap();
+    let view = String::from_utf8_lossy(&view.stdout).to_string();
+    assert!(!view.contains("120k"));
+
+    // Move the redacted item below its sibling
+    let moved = view.replace("- Lunch\n", "");
+    let moved = format!("- Lunch\n{moved}");
+    bin("safe-write")
+        .args(["write", file.to_str().unwrap()])
+        .write_stdin(moved)
+        .assert()
+        .success();
+    assert_eq!(
+        fs::read_to_string(&file).unwrap(),
+        "- Lunch\n- Salary #tlp/red\n  - Offer: 120k\n    - Bonus\n"
+    );
+}
+
+// ─── Write mode: multi-line secrets ───
+
+#[test]
+fn write_restores_multiline_secrets_exactly() {
+    let dir = tempdir().unwrap();
+    let file = dir.path().join("test.md");
+    let original = include_str!("fixtures/content/with_private_key.md");
+    fs::write(&file, original).unwrap();
+
+    let view = bin("safe-read")
+        .arg(file.to_str().unwrap())
+        .output()
+        .unwrap();
+    let edited = String::from_utf8_lossy(&view.stdout).replace("Deploy notes", "Deploy runbook");
+
+    bin("safe-write")
+        .args(["write", file.to_str().unwrap()])
+        .write_stdin(edited)
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("2 secret(s)"));
+
+    let result = fs::read_to_string(&file).unwrap();
+    assert_eq!(result, original.replace("Deploy notes", "Deploy runbook"));
+}
+
 // ─── Write mode: idempotency (safe-read → safe-write = original) ───
 
 #[test]
@@ -612,16 +1223,14 @@ fn write_roundtrip_preserves_original() {
     fs::write(&file, CONTENT_WITH_TLP_BLOCK).unwrap();
 
     // Step 1: safe-read to get the view
-    let safe_output = Command::cargo_bin("safe-read")
-        .unwrap()
+    let safe_output = bin("safe-read")
         .arg(file.to_str().unwrap())
         .output()
         .unwrap();
     let safe_view = String::from_utf8_lossy(&safe_output.stdout);
 
     // Step 2: safe-write back unchanged
-    Command::cargo_bin("safe-write")
-        .unwrap()
+    bin("safe-write")
         .args(["write", file.to_str().unwrap()])
         .write_stdin(safe_view.as_ref())
         .assert()
@@ -638,15 +1247,13 @@ fn write_roundtrip_with_secret_preserves_original() {
     let file = dir.path().join("test.md");
     fs::write(&file, CONTENT_WITH_SECRET).unwrap();
 
-    let safe_output = Command::cargo_bin("safe-read")
-        .unwrap()
+    let safe_output = bin("safe-read")
         .arg(file.to_str().unwrap())
         .output()
         .unwrap();
     let safe_view = String::from_utf8_lossy(&safe_output.stdout);
 
-    Command::cargo_bin("safe-write")
-        .unwrap()
+    bin("safe-write")
         .args(["write", file.to_str().unwrap()])
         .write_stdin(safe_view.as_ref())
         .assert()
@@ -662,15 +1269,13 @@ fn write_roundtrip_complex_preserves_original() {
     let file = dir.path().join("test.md");
     fs::write(&file, CONTENT_WITH_BLOCK_AND_SECRET).unwrap();
 
-    let safe_output = Command::cargo_bin("safe-read")
-        .unwrap()
+    let safe_output = bin("safe-read")
         .arg(file.to_str().unwrap())
         .output()
         .unwrap();
     let safe_view = String::from_utf8_lossy(&safe_output.stdout);
 
-    Command::cargo_bin("safe-write")
-        .unwrap()
+    bin("safe-write")
         .args(["write", file.to_str().unwrap()])
         .write_stdin(safe_view.as_ref())
         .assert()
@@ -689,8 +1294,7 @@ fn write_rejects_extra_flags() {
     fs::write(&file, "Original content.\n").unwrap();
 
     // --content flag doesn't exist for write mode
-    Command::cargo_bin("safe-write")
-        .unwrap()
+    bin("safe-write")
         .args([
             "write",
             file.to_str().unwrap(),
@@ -713,8 +1317,7 @@ fn write_rejects_extra_positional_args() {
     fs::write(&file, "Original.\n").unwrap();
 
     // Extra positional arg after file path
-    Command::cargo_bin("safe-write")
-        .unwrap()
+    bin("safe-write")
         .args(["write", file.to_str().unwrap(), "--stdin"])
         .write_stdin("Content.\n")
         .assert()
@@ -732,8 +1335,7 @@ fn edit_multiline_old_string() {
     let file = dir.path().join("test.md");
     fs::write(&file, "Line 1\nLine 2\nLine 3\n").unwrap();
 
-    Command::cargo_bin("safe-write")
-        .unwrap()
+    bin("safe-write")
         .args([
             "edit",
             file.to_str().unwrap(),
@@ -755,8 +1357,7 @@ fn edit_preserves_secrets_in_file() {
     let file = dir.path().join("test.md");
     fs::write(&file, CONTENT_WITH_SECRET).unwrap();
 
-    Command::cargo_bin("safe-write")
-        .unwrap()
+    bin("safe-write")
         .args([
             "edit",
             file.to_str().unwrap(),
@@ -775,8 +1376,7 @@ fn edit_preserves_secrets_in_file() {
 
 #[test]
 fn edit_nonexistent_file() {
-    Command::cargo_bin("safe-write")
-        .unwrap()
+    bin("safe-write")
         .args([
             "edit",
             "/tmp/definitely-does-not-exist-12345.md",
@@ -797,16 +1397,14 @@ fn edit_missing_flags() {
     fs::write(&file, "content").unwrap();
 
     // Missing --new
-    Command::cargo_bin("safe-write")
-        .unwrap()
+    bin("safe-write")
         .args(["edit", file.to_str().unwrap(), "--old", "content"])
         .assert()
         .code(1)
         .stderr(predicate::str::contains("--new is required"));
 
     // Missing --old
-    Command::cargo_bin("safe-write")
-        .unwrap()
+    bin("safe-write")
         .args(["edit", file.to_str().unwrap(), "--new", "replacement"])
         .assert()
         .code(1)
@@ -819,8 +1417,7 @@ fn edit_unknown_flag() {
     let file = dir.path().join("test.md");
     fs::write(&file, "content").unwrap();
 
-    Command::cargo_bin("safe-write")
-        .unwrap()
+    bin("safe-write")
         .args(["edit", file.to_str().unwrap(), "--bad", "value"])
         .assert()
         .code(1)
@@ -829,8 +1426,7 @@ fn edit_unknown_flag() {
 
 #[test]
 fn unknown_mode_exits_1() {
-    Command::cargo_bin("safe-write")
-        .unwrap()
+    bin("safe-write")
         .args(["delete", "/tmp/file.md"])
         .assert()
         .code(1)
@@ -848,8 +1444,7 @@ fn write_restores_secret_preserving_surrounding_text() {
 
     let new_content = "Config: token=[SECRET REDACTED] endpoint=staging\n";
 
-    Command::cargo_bin("safe-write")
-        .unwrap()
+    bin("safe-write")
         .args(["write", file.to_str().unwrap()])
         .write_stdin(new_content)
         .assert()
@@ -868,8 +1463,7 @@ fn write_fails_on_extra_secret_marker_in_plain_file() {
     let file = dir.path().join("test.md");
     fs::write(&file, "Normal content.\n").unwrap();
 
-    Command::cargo_bin("safe-write")
-        .unwrap()
+    bin("safe-write")
         .args(["write", file.to_str().unwrap()])
         .write_stdin("Has [SECRET REDACTED] spuriously.\n")
         .assert()
@@ -877,6 +1471,24 @@ fn write_fails_on_extra_secret_marker_in_plain_file() {
         .stderr(predicate::str::contains("no hidden content"));
 }
 
+#[test]
+fn write_refuses_file_with_literal_placeholder() {
+    let dir = tempdir().unwrap();
+    let file = dir.path().join("test.md");
+    let original = "See [REDACTED#1] in the docs.\n#tlp/red\nSecret line\n#tlp/amber\n";
+    fs::write(&file, original).unwrap();
+
+    bin("safe-write")
+        .args(["write", file.to_str().unwrap()])
+        .write_stdin("See [REDACTED#1] in the docs.\n[REDACTED#1]\n")
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains(
+            "looks like a redaction placeholder",
+        ));
+    assert_eq!(fs::read_to_string(&file).unwrap(), original);
+}
+
 // ─── Write mode: file outside vault ───
 
 #[test]
@@ -885,8 +1497,7 @@ fn write_works_on_file_outside_vault() {
     let file = dir.path().join("standalone.md");
     fs::write(&file, "Original.\n").unwrap();
 
-    Command::cargo_bin("safe-write")
-        .unwrap()
+    bin("safe-write")
         .args(["write", file.to_str().unwrap()])
         .write_stdin("Replaced.\n")
         .assert()
@@ -901,8 +1512,7 @@ fn edit_works_on_file_outside_vault() {
     let file = dir.path().join("standalone.md");
     fs::write(&file, "Hello world.\n").unwrap();
 
-    Command::cargo_bin("safe-write")
-        .unwrap()
+    bin("safe-write")
         .args([
             "edit",
             file.to_str().unwrap(),
@@ -925,8 +1535,7 @@ fn edit_not_found_shows_reread_hint() {
     let file = dir.path().join("test.md");
     fs::write(&file, CONTENT_PLAIN).unwrap();
 
-    Command::cargo_bin("safe-write")
-        .unwrap()
+    bin("safe-write")
         .args([
             "edit",
             file.to_str().unwrap(),
@@ -949,8 +1558,7 @@ fn insert_before_marker() {
     let file = dir.path().join("test.md");
     fs::write(&file, "Line 1\n![[Daily.base]]\n").unwrap();
 
-    Command::cargo_bin("safe-write")
-        .unwrap()
+    bin("safe-write")
         .args([
             "insert",
             file.to_str().unwrap(),
@@ -967,14 +1575,49 @@ fn insert_before_marker() {
     assert_eq!(result, "Line 1\nNew entry\n![[Daily.base]]\n");
 }
 
+#[test]
+fn insert_matches_visible_lines_only() {
+    let dir = tempdir().unwrap();
+    let file = dir.path().join("test.md");
+    fs::write(&file, CONTENT_WITH_TLP_BLOCK).unwrap();
+
+    bin("safe-write")
+        .args([
+            "insert",
+            file.to_str().unwrap(),
+            "--after",
+            "This is secret line A.",
+            "--content",
+            "x",
+        ])
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains("marker not found"));
+
+    bin("safe-write")
+        .args([
+            "insert",
+            file.to_str().unwrap(),
+            "--before",
+            "Visible bottom.",
+            "--content",
+            "New line.",
+        ])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("secret line").not());
+
+    let expected = CONTENT_WITH_TLP_BLOCK.replace("Visible bottom.", "New line.\nVisible bottom.");
+    assert_eq!(fs::read_to_string(&file).unwrap(), expected);
+}
+
 #[test]
 fn insert_after_marker() {
     let dir = tempdir().unwrap();
     let file = dir.path().join("test.md");
     fs::write(&file, "## Log\nExisting entry\n").unwrap();
 
-    Command::cargo_bin("safe-write")
-        .unwrap()
+    bin("safe-write")
         .args([
             "insert",
             file.to_str().unwrap(),
@@ -997,8 +1640,7 @@ fn insert_trimmed_match() {
     let file = dir.path().join("test.md");
     fs::write(&file, "Line 1\n  ![[Daily.base]]  \nLine 3\n").unwrap();
 
-    Command::cargo_bin("safe-write")
-        .unwrap()
+    bin("safe-write")
         .args([
             "insert",
             file.to_str().unwrap(),
@@ -1020,8 +1662,7 @@ fn insert_multiline_content() {
     let file = dir.path().join("test.md");
     fs::write(&file, "Top\n![[embed]]\nBottom\n").unwrap();
 
-    Command::cargo_bin("safe-write")
-        .unwrap()
+    bin("safe-write")
         .args([
             "insert",
             file.to_str().unwrap(),
@@ -1045,8 +1686,7 @@ fn insert_marker_not_found() {
     let file = dir.path().join("test.md");
     fs::write(&file, "Line 1\nLine 2\n").unwrap();
 
-    Command::cargo_bin("safe-write")
-        .unwrap()
+    bin("safe-write")
         .args([
             "insert",
             file.to_str().unwrap(),
@@ -1066,8 +1706,7 @@ fn insert_marker_ambiguous() {
     let file = dir.path().join("test.md");
     fs::write(&file, "marker\nother\nmarker\n").unwrap();
 
-    Command::cargo_bin("safe-write")
-        .unwrap()
+    bin("safe-write")
         .args([
             "insert",
             file.to_str().unwrap(),
@@ -1088,8 +1727,7 @@ fn insert_refuses_red_file() {
     let file = dir.path().join("secret.md");
     fs::write(&file, CONTENT_FRONTMATTER_RED).unwrap();
 
-    Command::cargo_bin("safe-write")
-        .unwrap()
+    bin("safe-write")
         .args([
             "insert",
             file.to_str().unwrap(),
@@ -1109,8 +1747,7 @@ fn insert_rejects_redaction_markers_in_content() {
     let file = dir.path().join("test.md");
     fs::write(&file, "Line 1\nmarker\n").unwrap();
 
-    Command::cargo_bin("safe-write")
-        .unwrap()
+    bin("safe-write")
         .args([
             "insert",
             file.to_str().unwrap(),
@@ -1123,8 +1760,7 @@ fn insert_rejects_redaction_markers_in_content() {
         .code(1)
         .stderr(predicate::str::contains("redaction markers"));
 
-    Command::cargo_bin("safe-write")
-        .unwrap()
+    bin("safe-write")
         .args([
             "insert",
             file.to_str().unwrap(),
@@ -1144,8 +1780,7 @@ fn insert_missing_position_flag() {
     let file = dir.path().join("test.md");
     fs::write(&file, "content\n").unwrap();
 
-    Command::cargo_bin("safe-write")
-        .unwrap()
+    bin("safe-write")
         .args(["insert", file.to_str().unwrap(), "--content", "text"])
         .assert()
         .code(1)
@@ -1158,8 +1793,7 @@ fn insert_both_position_flags() {
     let file = dir.path().join("test.md");
     fs::write(&file, "marker\n").unwrap();
 
-    Command::cargo_bin("safe-write")
-        .unwrap()
+    bin("safe-write")
         .args([
             "insert",
             file.to_str().unwrap(),
@@ -1184,8 +1818,7 @@ fn insert_unescapes_bang_in_marker() {
     fs::write(&file, "Top\n![[Daily.base]]\nBottom\n").unwrap();
 
     // Simulate what the shell delivers: \! instead of !
-    Command::cargo_bin("safe-write")
-        .unwrap()
+    bin("safe-write")
         .args([
             "insert",
             file.to_str().unwrap(),
@@ -1207,8 +1840,7 @@ fn insert_unescapes_bang_in_content() {
     let file = dir.path().join("test.md");
     fs::write(&file, "Top\nmarker\nBottom\n").unwrap();
 
-    Command::cargo_bin("safe-write")
-        .unwrap()
+    bin("safe-write")
         .args([
             "insert",
             file.to_str().unwrap(),
@@ -1230,8 +1862,7 @@ fn edit_unescapes_bang_in_old_and_new() {
     let file = dir.path().join("test.md");
     fs::write(&file, "Before ![[old-embed]] after.\n").unwrap();
 
-    Command::cargo_bin("safe-write")
-        .unwrap()
+    bin("safe-write")
         .args([
             "edit",
             file.to_str().unwrap(),
@@ -1254,8 +1885,7 @@ fn insert_literal_bang_still_works() {
     let file = dir.path().join("test.md");
     fs::write(&file, "![[embed]]\n").unwrap();
 
-    Command::cargo_bin("safe-write")
-        .unwrap()
+    bin("safe-write")
         .args([
             "insert",
             file.to_str().unwrap(),
@@ -1279,8 +1909,7 @@ fn edit_prints_diff_to_stderr() {
     let file = dir.path().join("test.md");
     fs::write(&file, CONTENT_PLAIN).unwrap();
 
-    Command::cargo_bin("safe-write")
-        .unwrap()
+    bin("safe-write")
         .args([
             "edit",
             file.to_str().unwrap(),
@@ -1304,8 +1933,7 @@ fn insert_prints_diff_to_stderr() {
     let file = dir.path().join("test.md");
     fs::write(&file, "Line 1\n![[Daily.base]]\n").unwrap();
 
-    Command::cargo_bin("safe-write")
-        .unwrap()
+    bin("safe-write")
         .args([
             "insert",
             file.to_str().unwrap(),
@@ -1326,8 +1954,7 @@ fn write_prints_diff_to_stderr() {
     let file = dir.path().join("test.md");
     fs::write(&file, "Original line.\n").unwrap();
 
-    Command::cargo_bin("safe-write")
-        .unwrap()
+    bin("safe-write")
         .args(["write", file.to_str().unwrap()])
         .write_stdin("Modified line.\n")
         .assert()
@@ -1354,8 +1981,7 @@ Modified top.
 Modified bottom.
 ";
 
-    let output = Command::cargo_bin("safe-write")
-        .unwrap()
+    let output = bin("safe-write")
         .args(["write", file.to_str().unwrap()])
         .write_stdin(new_content)
         .output()
@@ -1377,8 +2003,7 @@ fn edit_quiet_suppresses_diff() {
     let file = dir.path().join("test.md");
     fs::write(&file, CONTENT_PLAIN).unwrap();
 
-    Command::cargo_bin("safe-write")
-        .unwrap()
+    bin("safe-write")
         .args([
             "edit",
             file.to_str().unwrap(),
@@ -1410,8 +2035,7 @@ Modified top.
 Modified bottom.
 ";
 
-    Command::cargo_bin("safe-write")
-        .unwrap()
+    bin("safe-write")
         .args(["write", file.to_str().unwrap(), "-q"])
         .write_stdin(new_content)
         .assert()
@@ -1427,8 +2051,7 @@ fn insert_quiet_suppresses_diff() {
     let file = dir.path().join("test.md");
     fs::write(&file, "Line 1\nmarker\n").unwrap();
 
-    Command::cargo_bin("safe-write")
-        .unwrap()
+    bin("safe-write")
         .args([
             "insert",
             file.to_str().unwrap(),
@@ -1451,8 +2074,7 @@ fn write_roundtrip_no_diff() {
     let file = dir.path().join("test.md");
     fs::write(&file, "Unchanged content.\n").unwrap();
 
-    Command::cargo_bin("safe-write")
-        .unwrap()
+    bin("safe-write")
         .args(["write", file.to_str().unwrap()])
         .write_stdin("Unchanged content.\n")
         .assert()
@@ -1468,8 +2090,7 @@ fn edit_human_flag() {
     let file = dir.path().join("test.md");
     fs::write(&file, CONTENT_PLAIN).unwrap();
 
-    let output = Command::cargo_bin("safe-write")
-        .unwrap()
+    let output = bin("safe-write")
         .args([
             "edit",
             file.to_str().unwrap(),
@@ -1485,7 +2106,7 @@ fn edit_human_flag() {
     assert!(output.status.success());
     let stderr = String::from_utf8_lossy(&output.stderr);
     // Human format uses :N line numbers, not @@ hunks
-    assert!(stderr.contains(":"), "expected line number prefix ':'");
+    assert!(stderr.contains(':'), "expected line number prefix ':'");
     assert!(stderr.contains("- Hello world.") || stderr.contains("-Hello world."));
     assert!(stderr.contains("+ Goodbye world.") || stderr.contains("+Goodbye world."));
     assert!(!stderr.contains("@@"), "human format should not contain @@");
@@ -0,0 +1,375 @@
+#![allow(deprecated)] // Command::cargo_bin is the standard assert_cmd API
+
+use assert_cmd::Command;
+use context_tlp::approval::{self, Grant};
+use predicates::prelude::*;
+use std::fs;
+use std::path::Path;
+use tempfile::tempdir;
+
+// ─── Fixture constants ───
+
+const CONFIG_RED_CONTACTS: &str = include_str!("fixtures/configs/red_contacts.tlp");
+const CONFIG_AMBER_MD: &str = include_str!("fixtures/configs/amber_md.tlp");
+
+const CONTENT_FULL_PIPELINE: &str = include_str!("fixtures/content/full_pipeline.md");
+
+// ─── Helpers ───
+
+thread_local! {
+    /// The test harness runs each test on its own thread, so every test gets a
+    /// state dir of its own, removed when the test ends.
+    static STATE_DIR: tempfile::TempDir = tempdir().expect("create state dir");
+}
+
+/// Spawn a binary with an isolated `TLP_STATE_DIR`, so tests never touch the
+/// real audit log, pins, approvals, key or daemon socket.
+fn bin(name: &str) -> Command {
+    let mut cmd = Command::cargo_bin(name).expect("binary is built");
+    STATE_DIR.with(|dir| cmd.env("TLP_STATE_DIR", dir.path()));
+    cmd
+}
+
+fn hook_input(session_id: &str, tool_name: &str, file_path: &str) -> String {
+    format!(
+        r#"{{"session_id":"{session_id}","tool_name":"{tool_name}","tool_input":{{"file_path":"{file_path}"}}}}"#
+    )
+}
+
+fn guard(state: &std::path::Path, input: String) {
+    bin("tlp-guard")
+        .env("TLP_STATE_DIR", state)
+        .write_stdin(input)
+        .output()
+        .unwrap();
+}
+
+fn audit(state: &std::path::Path, args: &[&str]) -> String {
+    let output = bin("tlp")
+        .env("TLP_STATE_DIR", state)
+        .arg("audit")
+        .args(args)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    String::from_utf8_lossy(&output.stdout).to_string()
+}
+
+// ─── Basic usage ───
+
+#[test]
+fn no_args_exits_1_with_usage() {
+    bin("tlp")
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains("Usage"));
+}
+
+#[test]
+fn unknown_command_exits_1() {
+    bin("tlp")
+        .arg("frobnicate")
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains("Unknown command"));
+}
+
+// ─── Audit log ───
+
+#[test]
+fn guard_decisions_are_logged() {
+    let state = tempdir().unwrap();
+    let vault = tempdir().unwrap();
+    fs::write(vault.path().join(".tlp"), CONFIG_RED_CONTACTS).unwrap();
+    fs::create_dir_all(vault.path().join("Contacts")).unwrap();
+    fs::write(vault.path().join("Contacts/john.md"), "phone").unwrap();
+    let file = vault.path().join("Contacts/john.md");
+
+    guard(
+        state.path(),
+        hook_input("s1", "Read", file.to_str().unwrap()),
+    );
+
+    let log = fs::read_to_string(state.path().join("audit.jsonl")).unwrap();
+    let record: serde_json::Value = serde_json::from_str(log.trim()).unwrap();
+    assert_eq!(record["session_id"], "s1");
+    assert_eq!(record["tool"], "Read");
+    assert_eq!(record["path"], file.to_str().unwrap());
+    assert_eq!(record["rel_path"], "Contacts/john.md");
+    assert_eq!(record["level"], "RED");
+    assert_eq!(record["rule"], "Contacts/**");
+    assert_eq!(record["decision"], "deny");
+}
+
+#[test]
+fn files_outside_vault_are_not_logged() {
+    let state = tempdir().unwrap();
+    let dir = tempdir().unwrap();
+    let file = dir.path().join("note.md");
+    fs::write(&file, "hello").unwrap();
+
+    guard(
+        state.path(),
+        hook_input("s1", "Read", file.to_str().unwrap()),
+    );
+
+    assert!(!state.path().join("audit.jsonl").exists());
+}
+
+#[test]
+fn safe_read_logs_counts_and_session() {
+    let state = tempdir().unwrap();
+    let vault = tempdir().unwrap();
+    fs::write(vault.path().join(".tlp"), CONFIG_AMBER_MD).unwrap();
+    let file = vault.path().join("journal.md");
+    fs::write(&file, CONTENT_FULL_PIPELINE).unwrap();
+    grant(state.path(), file.to_str().unwrap(), Some("s7"), None);
+
+    bin("safe-read")
+        .env("TLP_STATE_DIR", state.path())
+        .env("TLP_SESSION_ID", "s7")
+        .arg(file.to_str().unwrap())
+        .assert()
+        .success();
+
+    let out = audit(state.path(), &["--json"]);
+    let record: serde_json::Value = serde_json::from_str(out.trim()).unwrap();
+    assert_eq!(record["tool"], "safe-read");
+    assert_eq!(record["session_id"], "s7");
+    assert_eq!(record["decision"], "allow");
+    assert_eq!(record["redactions"], 1);
+    assert_eq!(record["secrets"], 1);
+}
+
+#[test]
+fn audit_disabled_in_config() {
+    let state = tempdir().unwrap();
+    let vault = tempdir().unwrap();
+    fs::write(
+        vault.path().join(".tlp"),
+        "AMBER:\n  - \"*.md\"\n\naudit:\n  enabled: false\n",
+    )
+    .unwrap();
+    let file = vault.path().join("journal.md");
+    fs::write(&file, "diary").unwrap();
+
+    guard(
+        state.path(),
+        hook_input("s1", "Read", file.to_str().unwrap()),
+    );
+
+    assert!(!state.path().join("audit.jsonl").exists());
+}
+
+#[test]
+fn audit_filters_by_session_path_and_decision() {
+    let state = tempdir().unwrap();
+    let vault = tempdir().unwrap();
+    fs::write(vault.path().join(".tlp"), CONFIG_AMBER_MD).unwrap();
+    fs::write(vault.path().join("a.md"), "a").unwrap();
+    fs::write(vault.path().join("b.md"), "b").unwrap();
+    let a = vault.path().join("a.md");
+    let b = vault.path().join("b.md");
+
+    guard(state.path(), hook_input("s1", "Read", a.to_str().unwrap()));
+    guard(state.path(), hook_input("s1", "Edit", b.to_str().unwrap()));
+    guard(state.path(), hook_input("s2", "Read", b.to_str().unwrap()));
+
+    assert_eq!(audit(state.path(), &[]).lines().count(), 3);
+    assert_eq!(audit(state.path(), &["--session", "s1"]).lines().count(), 2);
+    assert_eq!(audit(state.path(), &["--path", "b.md"]).lines().count(), 2);
+
+    let denied = audit(state.path(), &["--decision", "deny"]);
+    assert_eq!(denied.lines().count(), 2);
+    assert!(denied.contains("deny"));
+    assert!(!denied.contains("allow"));
+
+    let edits = audit(state.path(), &["--decision", "allow", "--session", "s1"]);
+    assert!(edits.contains("Edit"));
+    assert!(edits.contains("b.md"));
+}
+
+#[test]
+fn audit_rejects_unknown_decision() {
+    let state = tempdir().unwrap();
+
+    bin("tlp")
+        .env("TLP_STATE_DIR", state.path())
+        .args(["audit", "--decision", "maybe"])
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains("Unknown decision"));
+}
+
+// ─── AMBER approvals ───
+
+fn tlp(state: &std::path::Path, args: &[&str]) -> assert_cmd::assert::Assert {
+    bin("tlp").env("TLP_STATE_DIR", state).args(args).assert()
+}
+
+/// Record a signed grant the way `tlp allow` does from a terminal.
+fn grant(state: &Path, pattern: &str, session: Option<&str>, minutes: Option<u64>) -> Grant {
+    let key = approval::load_or_create_key(&state.join("approval.key")).unwrap();
+    let now = context_tlp::state::now();
+    let mut grant = Grant {
+        pattern: pattern.to_string(),
+        session_id: session.map(String::from),
+        expires: minutes.map(|m| now + m * 60),
+        granted: now,
+        reveal: Vec::new(),
+        mac: String::new(),
+    };
+    grant.sign(&key);
+
+    let path = state.join("approvals.json");
+    let mut grants = approval::load(&path);
+    grants.push(grant.clone());
+    approval::save(&path, &grants, now).unwrap();
+    grant
+}
+
+#[test]
+fn approve_requires_a_terminal() {
+    let state = tempdir().unwrap();
+
+    tlp(state.path(), &["approve", "/vault/a.md"])
+        .code(1)
+        .stderr(predicate::str::contains("interactive terminal"));
+    assert!(!state.path().join("approval.key").exists());
+}
+
+#[test]
+fn approve_rejects_bad_minutes() {
+    let state = tempdir().unwrap();
+
+    tlp(
+        state.path(),
+        &["approve", "/vault/a.md", "--minutes", "soon"],
+    )
+    .code(1)
+    .stderr(predicate::str::contains("whole number"));
+}
+
+#[test]
+fn approvals_lists_scope_and_expiry() {
+    let state = tempdir().unwrap();
+    grant(state.path(), "/vault/Journals/**", Some("s42"), None);
+    grant(state.path(), "/vault/Identity.md", None, Some(30));
+
+    tlp(state.path(), &["approvals"])
+        .success()
+        .stdout(predicate::str::contains(
+            "/vault/Journals/** for session s42",
+        ))
+        .stdout(predicate::str::contains("/vault/Identity.md until"));
+}
+
+#[test]
+fn allow_without_scope_or_session_fails() {
+    let state = tempdir().unwrap();
+
+    tlp(state.path(), &["allow", "/vault/Journals/**"])
+        .code(1)
+        .stderr(predicate::str::contains("No active session"));
+}
+
+#[test]
+fn allow_requires_a_terminal() {
+    let state = tempdir().unwrap();
+    fs::write(state.path().join("session"), "s42").unwrap();
+
+    tlp(state.path(), &["allow", "/vault/Journals/**"])
+        .code(1)
+        .stderr(predicate::str::contains("interactive terminal"));
+    assert!(!state.path().join("approvals.json").exists());
+}
+
+#[test]
+fn allow_rejects_bad_minutes() {
+    let state = tempdir().unwrap();
+
+    tlp(state.path(), &["allow", "/vault/a.md", "--minutes", "soon"])
+        .code(1)
+        .stderr(predicate::str::contains("whole number"));
+}
+
+#[test]
+fn break_glass_requires_a_terminal() {
+    let state = tempdir().unwrap();
+
+    tlp(state.path(), &["break-glass", "/vault/Contacts/anna.md"])
+        .code(1)
+        .stderr(predicate::str::contains("interactive terminal"));
+    assert!(!state.path().join("overrides.json").exists());
+}
+
+#[test]
+fn pin_requires_a_terminal() {
+    let state = tempdir().unwrap();
+
+    tlp(state.path(), &["pin", "/vault"])
+        .code(1)
+        .stderr(predicate::str::contains("interactive terminal"));
+    assert!(!state.path().join("pins.json").exists());
+}
+
+#[test]
+fn revoke_removes_matching_grant() {
+    let state = tempdir().unwrap();
+
+    grant(state.path(), "/vault/a.md", Some("s1"), None);
+    grant(state.path(), "/vault/b.md", Some("s1"), None);
+    tlp(state.path(), &["revoke", "/vault/a.md"])
+        .success()
+        .stdout(predicate::str::contains("Revoked 1"));
+
+    tlp(state.path(), &["approvals"])
+        .success()
+        .stdout(predicate::str::contains("/vault/a.md").not())
+        .stdout(predicate::str::contains("/vault/b.md"));
+
+    tlp(state.path(), &["revoke", "--all"])
+        .success()
+        .stdout(predicate::str::contains("Revoked 1"));
+}
+
+// ─── Session start ───
+
+#[test]
+fn session_start_exports_session_id() {
+    let dir = tempdir().unwrap();
+    let env_file = dir.path().join("env.sh");
+
+    bin("tlp")
+        .env("TLP_STATE_DIR", dir.path())
+        .env("CLAUDE_ENV_FILE", &env_file)
+        .arg("session-start")
+        .write_stdin(r#"{"session_id":"abc-123","hook_event_name":"SessionStart"}"#)
+        .assert()
+        .success();
+
+    assert_eq!(
+        fs::read_to_string(&env_file).unwrap(),
+        "export TLP_SESSION_ID=abc-123\n"
+    );
+    assert_eq!(
+        fs::read_to_string(dir.path().join("session")).unwrap(),
+        "abc-123"
+    );
+}
+
+#[test]
+fn session_start_rejects_unsafe_session_id() {
+    let dir = tempdir().unwrap();
+    let env_file = dir.path().join("env.sh");
+
+    bin("tlp")
+        .env("TLP_STATE_DIR", dir.path())
+        .env("CLAUDE_ENV_FILE", &env_file)
+        .arg("session-start")
+        .write_stdin(r#"{"session_id":"x; rm -rf ~"}"#)
+        .assert()
+        .success();
+
+    assert!(!env_file.exists());
+}
@@ -0,0 +1,238 @@
+#![allow(deprecated)] // Command::cargo_bin is the standard assert_cmd API
+
+use assert_cmd::Command;
+use context_tlp::approval::{self, Grant};
+use predicates::prelude::*;
+use serde_json::{json, Value};
+use std::fs;
+use tempfile::tempdir;
+
+// ─── Fixture constants ───
+
+const CONFIG_AMBER_MD: &str = include_str!("fixtures/configs/amber_md.tlp");
+
+const CONTENT_PLAIN: &str = include_str!("fixtures/content/plain.md");
+const CONTENT_WITH_REDACTION: &str = include_str!("fixtures/content/with_redaction.md");
+const CONTENT_WITH_API_KEY: &str = include_str!("fixtures/content/with_api_key.md");
+
+// ─── Helpers ───
+
+thread_local! {
+    /// The test harness runs each test on its own thread, so every test gets a
+    /// state dir of its own, removed when the test ends.
+    static STATE_DIR: tempfile::TempDir = tempdir().expect("create state dir");
+}
+
+/// Spawn a binary with an isolated `TLP_STATE_DIR`, so tests never touch the
+/// real audit log, pins, approvals, key or daemon socket.
+fn bin(name: &str) -> Command {
+    let mut cmd = Command::cargo_bin(name).expect("binary is built");
+    STATE_DIR.with(|dir| cmd.env("TLP_STATE_DIR", dir.path()));
+    cmd
+}
+
+fn hook_input(tool_name: &str, cwd: &str, tool_response: &Value) -> String {
+    json!({
+        "hook_event_name": "PostToolUse",
+        "tool_name": tool_name,
+        "tool_input": {},
+        "tool_response": tool_response,
+        "cwd": cwd,
+    })
+    .to_string()
+}
+
+fn vault_with(config: &str) -> tempfile::TempDir {
+    let dir = tempdir().expect("create tempdir");
+    fs::write(dir.path().join(".tlp"), config).expect("write .tlp");
+    dir
+}
+
+fn run(input: String) -> Value {
+    let output = bin("tlp-scan").write_stdin(input).output().unwrap();
+    assert!(output.status.success());
+    serde_json::from_slice(&output.stdout).expect("hook output is JSON")
+}
+
+// ─── Clean output ───
+
+#[test]
+fn clean_output_passes_silently() {
+    let vault = vault_with(CONFIG_AMBER_MD);
+    let cwd = vault.path().to_str().unwrap();
+
+    bin("tlp-scan")
+        .write_stdin(hook_input("Bash", cwd, &json!({"stdout": CONTENT_PLAIN})))
+        .assert()
+        .success()
+        .stdout("");
+}
+
+#[test]
+fn outside_vault_passes_silently() {
+    let dir = tempdir().unwrap();
+    let cwd = dir.path().to_str().unwrap();
+
+    bin("tlp-scan")
+        .write_stdin(hook_input(
+            "Bash",
+            cwd,
+            &json!({"stdout": CONTENT_WITH_API_KEY}),
+        ))
+        .assert()
+        .success()
+        .stdout("");
+}
+
+#[test]
+fn invalid_json_allows() {
+    bin("tlp-scan")
+        .write_stdin("this is not json")
+        .assert()
+        .success()
+        .stdout("");
+}
+
+// ─── Default action: redact ───
+
+#[test]
+fn redacts_secret_in_bash_output() {
+    let vault = vault_with(CONFIG_AMBER_MD);
+    let cwd = vault.path().to_str().unwrap();
+
+    let output = run(hook_input(
+        "Bash",
+        cwd,
+        &json!({"stdout": CONTENT_WITH_API_KEY}),
+    ));
+    let reason = output["reason"].as_str().unwrap();
+    assert_eq!(output["decision"], "block");
+    assert!(reason.contains("1 secret(s)"));
+    assert!(reason.contains("[SECRET REDACTED#1]"));
+    assert!(!reason.contains("sk-ant-api03"));
+}
+
+#[test]
+fn redacts_tlp_red_section_in_output() {
+    let vault = vault_with(CONFIG_AMBER_MD);
+    let cwd = vault.path().to_str().unwrap();
+
+    let output = run(hook_input("WebFetch", cwd, &json!(CONTENT_WITH_REDACTION)));
+    let reason = output["reason"].as_str().unwrap();
+    assert!(reason.contains("1 #tlp/red section(s)"));
+    assert!(reason.contains("[REDACTED#1]"));
+    assert!(!reason.contains("Secret line"));
+}
+
+#[test]
+fn replaces_mcp_tool_output() {
+    let vault = vault_with(CONFIG_AMBER_MD);
+    let cwd = vault.path().to_str().unwrap();
+
+    let response = json!([{"type": "text", "text": CONTENT_WITH_API_KEY}]);
+    let output = run(hook_input("mcp__notes__fetch", cwd, &response));
+    let updated = &output["hookSpecificOutput"]["updatedMCPToolOutput"];
+    let text = updated[0]["text"].as_str().unwrap();
+    assert!(text.contains("[SECRET REDACTED#1]"));
+    assert!(!text.contains("sk-ant-api03"));
+}
+
+// ─── Configured actions ───
+
+#[test]
+fn block_action_withholds_output() {
+    let vault = vault_with("AMBER:\n  - \"*.md\"\n\ntool_output:\n  Bash: block\n");
+    let cwd = vault.path().to_str().unwrap();
+
+    let output = run(hook_input(
+        "Bash",
+        cwd,
+        &json!({"stdout": CONTENT_WITH_API_KEY}),
+    ));
+    let reason = output["reason"].as_str().unwrap();
+    assert_eq!(output["decision"], "block");
+    assert!(reason.contains("withheld"));
+    assert!(!reason.contains("SECRET REDACTED"));
+}
+
+#[test]
+fn warn_action_adds_context() {
+    let vault = vault_with("tool_output:\n  default: warn\n");
+    let cwd = vault.path().to_str().unwrap();
+
+    let output = run(hook_input(
+        "Bash",
+        cwd,
+        &json!({"stdout": CONTENT_WITH_API_KEY}),
+    ));
+    assert!(output.get("decision").is_none());
+    let context = output["hookSpecificOutput"]["additionalContext"]
+        .as_str()
+        .unwrap();
+    assert!(context.contains("1 secret(s)"));
+    assert!(output["systemMessage"]
+        .as_str()
+        .is_some_and(|m| m.contains("TLP")));
+}
+
+#[test]
+fn unlisted_tool_uses_default_action() {
+    let vault = vault_with("tool_output:\n  default: block\n  Bash: warn\n");
+    let cwd = vault.path().to_str().unwrap();
+
+    bin("tlp-scan")
+        .write_stdin(hook_input("Grep", cwd, &json!(CONTENT_WITH_API_KEY)))
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("withheld"));
+}
+
+// ─── Revealed compartments ───
+
+#[test]
+fn grant_reveals_compartment_in_tool_output() {
+    let vault = vault_with(CONFIG_AMBER_MD);
+    let file = vault.path().join("notes.md");
+    let content = "Plan #tlp/red/health checkup.\nBudget #tlp/red/finance 20k.\n";
+    fs::write(&file, content).unwrap();
+    let file = file.to_str().unwrap();
+
+    let state = tempdir().unwrap();
+    let key = approval::load_or_create_key(&state.path().join("approval.key")).unwrap();
+    let now = context_tlp::state::now();
+    let mut grant = Grant {
+        pattern: file.to_string(),
+        session_id: Some("s1".to_string()),
+        expires: Some(now + 600),
+        granted: now,
+        reveal: vec!["health".to_string()],
+        mac: String::new(),
+    };
+    grant.sign(&key);
+    approval::save(&state.path().join("approvals.json"), &[grant], now).unwrap();
+
+    let scan = |session: &str| {
+        let input = json!({
+            "hook_event_name": "PostToolUse",
+            "session_id": session,
+            "tool_name": "Read",
+            "tool_input": {"file_path": file},
+            "tool_response": content,
+            "cwd": vault.path().to_str().unwrap(),
+        });
+        let output = bin("tlp-scan")
+            .env("TLP_STATE_DIR", state.path())
+            .write_stdin(input.to_string())
+            .output()
+            .unwrap();
+        let output: Value = serde_json::from_slice(&output.stdout).unwrap();
+        output["reason"].as_str().unwrap().to_string()
+    };
+
+    let reason = scan("s1");
+    assert!(reason.contains("checkup"));
+    assert!(!reason.contains("20k"));
+
+    // The grant belongs to another session
+    assert!(!scan("s2").contains("checkup"));
+}